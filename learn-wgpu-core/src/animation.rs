@@ -0,0 +1,561 @@
+//! Keyframe animation. A [`Track`] holds timed values of one property and
+//! samples them with an [`Interpolation`] mode and per-key [`Easing`]; a
+//! [`Clip`] binds tracks to graph nodes, lights and instance colors and
+//! repeats them once, in a loop or back and forth.
+//!
+//! Clips are pure functions of the animation time, which a [`Clock`]
+//! derives from the frame times and which can be paused, scrubbed and
+//! scaled. Tests evaluate them at any time directly.
+
+use std::time::Duration;
+
+use cgmath::{InnerSpace, Quaternion};
+use winit::{
+    event::{ElementState, KeyEvent, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::instance::Instance;
+use crate::lights::LightSource;
+use crate::scene_graph::{NodeId, SceneGraph};
+
+/// Seconds the arrow keys move the clock by.
+const SCRUB_STEP: f32 = 0.25;
+/// Range of [`Clock::time_scale`] reachable from the keyboard.
+const MIN_TIME_SCALE: f32 = 1.0 / 16.0;
+const MAX_TIME_SCALE: f32 = 16.0;
+
+/// Reshapes the progress `t` from 0 to 1 between two keyframes.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Starts slowly (cubic).
+    EaseIn,
+    /// Ends slowly (cubic).
+    EaseOut,
+    /// Starts and ends slowly (cubic).
+    EaseInOut,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - 0.5 * (2.0 - 2.0 * t).powi(3)
+                }
+            },
+        }
+    }
+}
+
+/// How a [`Track`] fills in values between keyframes.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Holds each value until the next keyframe.
+    Step,
+    #[default]
+    Linear,
+    /// A Catmull-Rom spline through the keyframes, so motion does not
+    /// change direction abruptly at them.
+    Cubic,
+    /// Along the shortest arc between rotations; linear for other values.
+    Slerp,
+}
+
+/// A value a [`Track`] can interpolate.
+pub trait Animatable: Copy {
+    fn lerp(a: Self, b: Self, t: f32) -> Self;
+
+    /// The Catmull-Rom spline from `p1` to `p2`, shaped by their neighbors
+    /// `p0` and `p3`.
+    fn cubic(p0: Self, p1: Self, p2: Self, p3: Self, t: f32) -> Self;
+
+    fn slerp(a: Self, b: Self, t: f32) -> Self {
+        Self::lerp(a, b, t)
+    }
+}
+
+impl Animatable for f32 {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a + (b - a) * t
+    }
+
+    fn cubic(p0: Self, p1: Self, p2: Self, p3: Self, t: f32) -> Self {
+        let (t2, t3) = (t * t, t * t * t);
+        0.5 * (
+            2.0 * p1
+                + (p2 - p0) * t
+                + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+                + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3
+        )
+    }
+}
+
+// positions, scales and colors, per component
+impl<const N: usize> Animatable for [f32; N] {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        std::array::from_fn(|i| f32::lerp(a[i], b[i], t))
+    }
+
+    fn cubic(p0: Self, p1: Self, p2: Self, p3: Self, t: f32) -> Self {
+        std::array::from_fn(|i| f32::cubic(p0[i], p1[i], p2[i], p3[i], t))
+    }
+}
+
+// unit quaternions; linear interpolation is normalized, and a spline
+// through rotations falls back to the arc between its middle keys
+impl Animatable for Quaternion<f32> {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        // through the shorter of the two equivalent ends
+        let b = if a.dot(b) < 0.0 { -b } else { b };
+        a.nlerp(b, t)
+    }
+
+    fn cubic(_p0: Self, p1: Self, p2: Self, _p3: Self, t: f32) -> Self {
+        p1.slerp(p2, t)
+    }
+
+    fn slerp(a: Self, b: Self, t: f32) -> Self {
+        a.slerp(b, t)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Keyframe<T> {
+    /// In seconds from the start of the clip.
+    pub time: f32,
+    pub value: T,
+    /// Applied to the way from this keyframe to the next.
+    pub easing: Easing,
+}
+
+/// Keyframes of one property, sorted by time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track<T> {
+    keyframes: Vec<Keyframe<T>>,
+    interpolation: Interpolation,
+}
+
+impl<T: Animatable> Track<T> {
+    pub fn new(interpolation: Interpolation) -> Self {
+        Self { keyframes: Vec::new(), interpolation }
+    }
+
+    /// Adds a keyframe eased linearly into the next one.
+    pub fn key(self, time: f32, value: T) -> Self {
+        self.eased_key(time, value, Easing::Linear)
+    }
+
+    /// Adds a keyframe. A keyframe at the time of an earlier one jumps to
+    /// its value.
+    pub fn eased_key(mut self, time: f32, value: T, easing: Easing) -> Self {
+        let index = self.keyframes.partition_point(|k| k.time <= time);
+        self.keyframes.insert(index, Keyframe { time, value, easing });
+        self
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// Time of the last keyframe.
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    /// The value at `time`, holding the first and last values outside the
+    /// keyframes. `None` without keyframes.
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let keys = &self.keyframes;
+        let next = keys.partition_point(|k| k.time <= time);
+        if next == 0 {
+            return keys.first().map(|k| k.value);
+        }
+        if next == keys.len() {
+            return keys.last().map(|k| k.value);
+        }
+        let (a, b) = (&keys[next - 1], &keys[next]);
+        let t = a.easing.apply((time - a.time) / (b.time - a.time));
+        Some(match self.interpolation {
+            Interpolation::Step => a.value,
+            Interpolation::Linear => T::lerp(a.value, b.value, t),
+            Interpolation::Slerp => T::slerp(a.value, b.value, t),
+            Interpolation::Cubic => {
+                // the end keyframes stand in for missing neighbors
+                let before = keys[next.saturating_sub(2)].value;
+                let after = keys.get(next + 1).unwrap_or(b).value;
+                T::cubic(before, a.value, b.value, after, t)
+            },
+        })
+    }
+}
+
+/// What a clip does after its last keyframe.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Repeat {
+    /// Holds the last values.
+    #[default]
+    Once,
+    /// Starts over.
+    Loop,
+    /// Plays backwards to the start, then forwards again.
+    PingPong,
+}
+
+impl Repeat {
+    /// Maps `time` into `0..=duration`.
+    pub fn wrap(self, time: f32, duration: f32) -> f32 {
+        if duration <= 0.0 {
+            return 0.0;
+        }
+        match self {
+            Repeat::Once => time.clamp(0.0, duration),
+            Repeat::Loop => time.rem_euclid(duration),
+            Repeat::PingPong => {
+                let t = time.rem_euclid(2.0 * duration);
+                if t > duration { 2.0 * duration - t } else { t }
+            },
+        }
+    }
+}
+
+/// A track bound to the property it animates. Lights are indexed as
+/// passed to [`crate::common::State::set_lights`], instances as in
+/// [`crate::common::State::set_instances`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Channel {
+    Translation(NodeId, Track<[f32; 3]>),
+    Rotation(NodeId, Track<Quaternion<f32>>),
+    Scale(NodeId, Track<[f32; 3]>),
+    /// Linear RGBA of one instance of an object.
+    InstanceColor { object: usize, instance: usize, track: Track<[f32; 4]> },
+    LightPosition(usize, Track<[f32; 3]>),
+    LightDirection(usize, Track<[f32; 3]>),
+    LightColor(usize, Track<[f32; 3]>),
+    LightIntensity(usize, Track<f32>),
+}
+
+impl Channel {
+    pub fn duration(&self) -> f32 {
+        match self {
+            Channel::Translation(_, track)
+            | Channel::Scale(_, track)
+            | Channel::LightPosition(_, track)
+            | Channel::LightDirection(_, track)
+            | Channel::LightColor(_, track) => track.duration(),
+            Channel::Rotation(_, track) => track.duration(),
+            Channel::InstanceColor { track, .. } => track.duration(),
+            Channel::LightIntensity(_, track) => track.duration(),
+        }
+    }
+}
+
+/// Channels played together on one timeline.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Clip {
+    channels: Vec<Channel>,
+    repeat: Repeat,
+}
+
+impl Clip {
+    pub fn new(repeat: Repeat) -> Self {
+        Self { channels: Vec::new(), repeat }
+    }
+
+    pub fn with(mut self, channel: Channel) -> Self {
+        self.channels.push(channel);
+        self
+    }
+
+    pub fn channels(&self) -> &[Channel] {
+        &self.channels
+    }
+
+    /// Time of the last keyframe of any channel.
+    pub fn duration(&self) -> f32 {
+        self.channels.iter().map(Channel::duration).fold(0.0, f32::max)
+    }
+
+    /// Where in the clip the animation time `time` falls.
+    pub fn local_time(&self, time: f32) -> f32 {
+        self.repeat.wrap(time, self.duration())
+    }
+
+    /// Sets the animated node transforms and light parameters to their
+    /// values at `time`. Channels of lights that do not exist are skipped.
+    pub fn apply(&self, time: f32, graph: &mut SceneGraph, lights: &mut [LightSource]) {
+        let t = self.local_time(time);
+        for channel in &self.channels {
+            match channel {
+                Channel::Translation(node, track) => if let Some(v) = track.sample(t) {
+                    graph.set_translation(*node, v);
+                },
+                Channel::Rotation(node, track) => if let Some(v) = track.sample(t) {
+                    graph.set_rotation(*node, v.normalize());
+                },
+                Channel::Scale(node, track) => if let Some(v) = track.sample(t) {
+                    graph.set_scale(*node, v);
+                },
+                Channel::LightPosition(light, track) => {
+                    if let (Some(light), Some(v)) = (lights.get_mut(*light), track.sample(t)) {
+                        light.set_position(v);
+                    }
+                },
+                Channel::LightDirection(light, track) => {
+                    if let (Some(light), Some(v)) = (lights.get_mut(*light), track.sample(t)) {
+                        light.set_direction(v);
+                    }
+                },
+                Channel::LightColor(light, track) => {
+                    if let (Some(light), Some(v)) = (lights.get_mut(*light), track.sample(t)) {
+                        light.set_color(v);
+                    }
+                },
+                Channel::LightIntensity(light, track) => {
+                    if let (Some(light), Some(v)) = (lights.get_mut(*light), track.sample(t)) {
+                        light.set_intensity(v);
+                    }
+                },
+                Channel::InstanceColor { .. } => {},
+            }
+        }
+    }
+
+    /// Sets the animated colors of the `object`th object's instances to
+    /// their values at `time`, and returns whether any were.
+    pub fn apply_instances(&self, time: f32, object: usize, instances: &mut [Instance]) -> bool {
+        let t = self.local_time(time);
+        let mut changed = false;
+        for channel in &self.channels {
+            if let Channel::InstanceColor { object: o, instance, track } = channel
+                && *o == object
+                && let (Some(instance), Some(color)) = (instances.get_mut(*instance), track.sample(t))
+            {
+                instance.color = color;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+/// Animation time in seconds, advanced by the frame times unless paused.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Clock {
+    time: f32,
+    paused: bool,
+    time_scale: f32,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self { time: 0.0, paused: false, time_scale: 1.0 }
+    }
+}
+
+impl Clock {
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// The time as passed to the animation callbacks.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_secs_f32(self.time)
+    }
+
+    /// Advances by `frame_time` seconds scaled by [`Clock::time_scale`];
+    /// negative frame times go back, but not before 0.
+    pub fn advance(&mut self, frame_time: f32) {
+        if !self.paused {
+            self.seek(self.time + frame_time * self.time_scale);
+        }
+    }
+
+    /// Jumps to `time`, which is clamped to be non-negative; also while
+    /// paused.
+    pub fn seek(&mut self, time: f32) {
+        self.time = time.max(0.0);
+    }
+
+    pub fn scrub(&mut self, offset: f32) {
+        self.seek(self.time + offset);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Animation seconds per real second; negative values are clamped
+    /// to 0.
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale.max(0.0);
+    }
+
+    /// Space pauses and resumes, the left and right arrows scrub, and the
+    /// brackets halve and double the time scale. Returns whether the event
+    /// was used.
+    pub fn process_event(&mut self, event: &WindowEvent) -> bool {
+        let WindowEvent::KeyboardInput {
+            event: KeyEvent {
+                state: ElementState::Pressed, physical_key: PhysicalKey::Code(key), repeat, ..
+            },
+            ..
+        } = event else {
+            return false;
+        };
+        match key {
+            KeyCode::Space if !repeat => {
+                self.paused = !self.paused;
+                println!("Animation {}", if self.paused { "paused" } else { "resumed" });
+            },
+            KeyCode::ArrowLeft => self.scrub(-SCRUB_STEP),
+            KeyCode::ArrowRight => self.scrub(SCRUB_STEP),
+            KeyCode::BracketLeft | KeyCode::BracketRight if !repeat => {
+                let factor = if *key == KeyCode::BracketLeft { 0.5 } else { 2.0 };
+                self.time_scale = (self.time_scale * factor).clamp(MIN_TIME_SCALE, MAX_TIME_SCALE);
+                println!("Animation speed: {}x", self.time_scale);
+            },
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{Deg, Rotation3, Vector4, assert_relative_eq};
+
+    #[test]
+    fn easing_keeps_the_ends() {
+        for easing in [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+        assert!(Easing::EaseIn.apply(0.25) < 0.25);
+        assert!(Easing::EaseOut.apply(0.25) > 0.25);
+        assert_relative_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+    }
+
+    #[test]
+    fn tracks_interpolate_between_keyframes() {
+        let keys = |interpolation| {
+            // inserted out of order
+            Track::new(interpolation).key(2.0, 4.0).key(0.0, 0.0).key(1.0, 1.0).key(3.0, 9.0)
+        };
+        let step = keys(Interpolation::Step);
+        assert_eq!(step.sample(1.9), Some(1.0));
+        assert_eq!(step.sample(-1.0), Some(0.0));
+        assert_eq!(step.sample(5.0), Some(9.0));
+        assert_eq!(keys(Interpolation::Linear).sample(1.5), Some(2.5));
+        assert_eq!(Track::<f32>::new(Interpolation::Linear).sample(1.0), None);
+
+        // the spline through keyframes of y = x^2 reproduces it between
+        // inner keyframes
+        let cubic = keys(Interpolation::Cubic);
+        for time in [0.0, 1.0, 2.0, 3.0] {
+            assert_relative_eq!(cubic.sample(time).unwrap(), time * time);
+        }
+        assert_relative_eq!(cubic.sample(1.5).unwrap(), 2.25);
+
+        let eased = Track::new(Interpolation::Linear)
+            .eased_key(0.0, [0.0, 10.0], Easing::EaseIn)
+            .key(1.0, [1.0, 20.0]);
+        assert_eq!(eased.sample(0.5), Some([0.125, 11.25]));
+    }
+
+    #[test]
+    fn rotation_tracks_follow_the_shortest_arc() {
+        let a = Quaternion::from_angle_y(Deg(0.0));
+        let b = Quaternion::from_angle_y(Deg(90.0));
+        // the same orientation as b, from the other side
+        let track = Track::new(Interpolation::Slerp).key(0.0, a).key(1.0, -b);
+        let half = track.sample(0.5).unwrap();
+        assert_relative_eq!(half, Quaternion::from_angle_y(Deg(45.0)), epsilon = 1e-5);
+        let linear = Track::new(Interpolation::Linear).key(0.0, a).key(1.0, -b);
+        assert_relative_eq!(linear.sample(0.5).unwrap(), half, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn repeat_wraps_time() {
+        assert_eq!(Repeat::Once.wrap(3.5, 2.0), 2.0);
+        assert_eq!(Repeat::Loop.wrap(3.5, 2.0), 1.5);
+        assert_eq!(Repeat::PingPong.wrap(3.5, 2.0), 0.5);
+        assert_eq!(Repeat::PingPong.wrap(4.5, 2.0), 0.5);
+        assert_eq!(Repeat::Loop.wrap(3.5, 0.0), 0.0);
+    }
+
+    #[test]
+    fn clips_are_evaluated_at_any_time() {
+        let mut graph = SceneGraph::new();
+        let node = graph.add_node(None, "node");
+        let mut lights = [LightSource::point([0.0; 3], [1.0; 3])];
+        let clip = Clip::new(Repeat::PingPong)
+            .with(Channel::Translation(
+                node, Track::new(Interpolation::Linear).key(0.0, [0.0; 3]).key(2.0, [4.0, 0.0, 0.0])
+            ))
+            .with(Channel::LightIntensity(0, Track::new(Interpolation::Step).key(0.0, 1.0).key(1.0, 3.0)))
+            // no such light
+            .with(Channel::LightColor(5, Track::new(Interpolation::Linear).key(0.0, [0.0; 3])));
+        assert_eq!(clip.duration(), 2.0);
+
+        // on the way back
+        clip.apply(3.5, &mut graph, &mut lights);
+        graph.update_world_matrices();
+        let p = graph.node(node).world_matrix() * Vector4::unit_w();
+        assert_relative_eq!(p.x, 1.0);
+        assert_eq!(lights[0], LightSource::point([0.0; 3], [1.0; 3]));
+        clip.apply(2.5, &mut graph, &mut lights);
+        assert_eq!(lights[0], LightSource::point([0.0; 3], [1.0; 3]).with_intensity(3.0));
+
+        let mut instances = [Instance::default(); 2];
+        let colors = Clip::new(Repeat::Once).with(Channel::InstanceColor {
+            object: 1, instance: 1,
+            track: Track::new(Interpolation::Linear).key(0.0, [0.0; 4]).key(1.0, [1.0; 4]),
+        });
+        assert!(!colors.apply_instances(0.5, 0, &mut instances));
+        assert!(colors.apply_instances(0.5, 1, &mut instances));
+        assert_eq!(instances[1].color, [0.5; 4]);
+        assert_eq!(instances[0], Instance::default());
+    }
+
+    #[test]
+    fn clock_pauses_scrubs_and_scales() {
+        let mut clock = Clock::default();
+        clock.advance(0.5);
+        clock.set_time_scale(2.0);
+        clock.advance(0.5);
+        assert_eq!(clock.time(), 1.5);
+        clock.advance(-1.0);
+        assert_eq!(clock.time(), 0.0);
+        clock.advance(0.75);
+        clock.set_paused(true);
+        clock.advance(1.0);
+        clock.scrub(-0.5);
+        assert_eq!(clock.time(), 1.0);
+        clock.scrub(-2.0);
+        assert_eq!(clock.time(), 0.0);
+        clock.set_time_scale(-1.0);
+        assert_eq!(clock.time_scale(), 0.0);
+    }
+}
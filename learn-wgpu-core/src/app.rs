@@ -0,0 +1,127 @@
+//! The window event loop shared by the renderers: Escape closes the window,
+//! F12 saves a screenshot and `--screenshot <path> [--frames <n>]` captures
+//! one frame and exits.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use winit::{
+    dpi::PhysicalSize,
+    event::{ElementState, Event, KeyEvent, WindowEvent},
+    event_loop::EventLoop,
+    keyboard::{KeyCode, PhysicalKey},
+    window::Window
+};
+
+use crate::screenshot::{self, ScreenshotArgs};
+
+/// A renderer driven by [`run_loop`].
+pub trait App {
+    /// Returns `true` if the event was consumed, e.g. by the camera.
+    fn input(&mut self, event: &WindowEvent) -> bool;
+
+    /// Called before every frame with the time since the loop started.
+    fn update(&mut self, dt: Duration);
+
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError>;
+
+    fn resize(&mut self, size: PhysicalSize<u32>);
+
+    /// Current surface size, restored when the surface is lost.
+    fn size(&self) -> PhysicalSize<u32>;
+
+    fn screenshot(&mut self) -> &mut screenshot::Pending;
+
+    /// A key press that neither [`App::input`] nor the loop handled.
+    fn key_pressed(&mut self, _key: KeyCode) {}
+
+    /// Called at the start of every frame, before [`App::update`].
+    fn begin_frame(&mut self) {}
+}
+
+/// Opens a window titled `title`.
+#[allow(deprecated)]
+pub fn create_window(title: &str) -> (EventLoop<()>, Arc<Window>) {
+    let window_attributes = Window::default_attributes();
+    let event_loop = EventLoop::new().unwrap();
+    let window = Arc::new(
+        event_loop.create_window(window_attributes).unwrap()
+    );
+    window.set_title(title);
+    (event_loop, window)
+}
+
+/// Renders `app` until the window is closed, or until the frame requested
+/// by `screenshot_args` has been saved.
+#[allow(deprecated)]
+pub fn run_loop(
+    event_loop: EventLoop<()>, mut app: impl App, screenshot_args: Option<ScreenshotArgs>
+) {
+    let mut frame_count = 0;
+
+    let start_time = Instant::now();
+    let _ = event_loop.run(move |event, elwt| {
+        if let Event::WindowEvent { ref event, .. } = event {
+            if app.input(event) {
+                return;
+            }
+            match event {
+                WindowEvent::CloseRequested
+                | WindowEvent::KeyboardInput {
+                    event: KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::Escape),
+                        ..
+                    },
+                    ..
+                } => elwt.exit(),
+                WindowEvent::KeyboardInput {
+                    event: KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::F12),
+                        repeat: false,
+                        ..
+                    },
+                    ..
+                } => app.screenshot().request(screenshot::timestamped_path()),
+                WindowEvent::KeyboardInput {
+                    event: KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(key),
+                        repeat: false,
+                        ..
+                    },
+                    ..
+                } => app.key_pressed(*key),
+                WindowEvent::RedrawRequested => {
+                    frame_count += 1;
+                    if let Some(args) = &screenshot_args
+                        && args.frames == frame_count
+                    {
+                        app.screenshot().request(args.path.clone());
+                    }
+                    app.begin_frame();
+                    app.update(start_time.elapsed());
+                    match app.render() {
+                        Ok(_) => {},
+                        Err(wgpu::SurfaceError::Lost) => {
+                            let size = app.size();
+                            app.resize(size);
+                        },
+                        Err(wgpu::SurfaceError::OutOfMemory)
+                            => elwt.exit(),
+                        Err(e) => eprintln!("{:?}", e),
+                    }
+                    if let Some(args) = &screenshot_args
+                        && frame_count >= args.frames
+                        && !app.screenshot().is_pending()
+                    {
+                        elwt.exit();
+                    }
+                },
+                WindowEvent::Resized(size) => app.resize(*size),
+                _ => {}
+            }
+        }
+    });
+}
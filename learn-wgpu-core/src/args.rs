@@ -0,0 +1,96 @@
+//! Command line of the model viewers: an optional model path and the
+//! viewer's own switches, next to the flags the renderer reads itself.
+
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::{config, shader};
+
+/// Flags read by [`crate::app`]'s event loop that take a value.
+const SCREENSHOT_FLAGS: &[&str] = &["--screenshot", "--frames"];
+
+/// An unknown flag or a second path, with the program's usage line.
+#[derive(Debug)]
+pub struct UsageError {
+    pub message: String,
+    pub usage: String,
+}
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\nusage: {}", self.message, self.usage)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ModelArgs {
+    pub path: Option<PathBuf>,
+    /// Those of the viewer's own switches that were given.
+    pub switches: Vec<String>,
+}
+
+impl ModelArgs {
+    /// Parses the process arguments, accepting `switches` besides the
+    /// renderer's flags. `usage` is shown on errors.
+    pub fn from_env(usage: &str, switches: &[&str]) -> Result<Self, UsageError> {
+        Self::from_args(std::env::args().skip(1), usage, switches)
+    }
+
+    /// Same as [`ModelArgs::from_env`], for the arguments after the program
+    /// name.
+    pub fn from_args(
+        args: impl IntoIterator<Item = String>, usage: &str, switches: &[&str]
+    ) -> Result<Self, UsageError> {
+        let error = |message: String| UsageError { message, usage: usage.to_string() };
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                _ if switches.contains(&arg.as_str()) => parsed.switches.push(arg),
+                // handled by the renderer
+                _ if SCREENSHOT_FLAGS.contains(&arg.as_str())
+                    || config::VALUE_FLAGS.contains(&arg.as_str()) => { args.next(); },
+                _ if config::SWITCH_FLAGS.contains(&arg.as_str()) => {},
+                shader::HOT_RELOAD_FLAG => {},
+                _ if arg.starts_with("--") => {
+                    return Err(error(format!("unknown flag {}", arg)));
+                },
+                _ if parsed.path.is_some() => {
+                    return Err(error(format!("unexpected argument {}", arg)));
+                },
+                _ => parsed.path = Some(PathBuf::from(arg)),
+            }
+        }
+        Ok(parsed)
+    }
+
+    pub fn has(&self, switch: &str) -> bool {
+        self.switches.iter().any(|s| s == switch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<ModelArgs, UsageError> {
+        ModelArgs::from_args(args.iter().map(|arg| arg.to_string()), "viewer [model]", &["--flat"])
+    }
+
+    #[test]
+    fn skips_renderer_flags() {
+        let args = parse(&[
+            "--backend", "gl", "model.obj", "--hot-reload", "--frames", "3", "--flat"
+        ]).unwrap();
+        assert_eq!(args.path, Some(PathBuf::from("model.obj")));
+        assert!(args.has("--flat"));
+        assert_eq!(parse(&[]).unwrap(), ModelArgs::default());
+
+        for args in [&["--smooth"][..], &["a.obj", "b.obj"]] {
+            let error = parse(args).unwrap_err();
+            assert!(error.to_string().ends_with("usage: viewer [model]"), "{}", error);
+        }
+    }
+}
@@ -0,0 +1,347 @@
+//! Interactive camera driven by winit mouse and keyboard events.
+//!
+//! - Orbit: left-drag rotates around the target, the wheel zooms and
+//!   middle-drag pans.
+//! - Arcball: left-drag rolls a virtual trackball centered on the target;
+//!   wheel and middle-drag as in orbit mode.
+//! - Fly: left-drag looks around, `WASD` moves, `Q`/`E` move down/up and the
+//!   wheel changes the speed.
+//!
+//! `C` cycles through the modes.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use cgmath::*;
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::transforms;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CameraMode {
+    Orbit,
+    Arcball,
+    Fly,
+}
+
+impl CameraMode {
+    fn next(self) -> Self {
+        match self {
+            CameraMode::Orbit => CameraMode::Arcball,
+            CameraMode::Arcball => CameraMode::Fly,
+            CameraMode::Fly => CameraMode::Orbit,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Camera {
+    pub eye: Point3<f32>,
+    pub target: Point3<f32>,
+    pub up: Vector3<f32>,
+}
+
+impl Camera {
+    pub fn new(eye: Point3<f32>, target: Point3<f32>, up: Vector3<f32>) -> Self {
+        Self { eye, target, up }
+    }
+
+    pub fn view_mat(&self) -> Matrix4<f32> {
+        transforms::create_view(self.eye, self.target, self.up)
+    }
+
+    fn forward(&self) -> Vector3<f32> {
+        (self.target - self.eye).normalize()
+    }
+
+    fn right(&self) -> Vector3<f32> {
+        self.forward().cross(self.up).normalize()
+    }
+
+    /// Rotates the eye around the target: `yaw` turns it left, `pitch` raises
+    /// it, stopping short of the poles.
+    pub fn orbit(&mut self, yaw: Rad<f32>, pitch: Rad<f32>) {
+        let offset = self.eye - self.target;
+        let max_pitch = Rad(0.49 * std::f32::consts::PI);
+        let current = Rad(offset.normalize().dot(self.up).clamp(-1.0, 1.0).asin());
+        let pitch = Rad((current + pitch).0.clamp(-max_pitch.0, max_pitch.0)) - current;
+        // turning about the right vector lowers the eye for positive angles
+        let rotation = Quaternion::from_axis_angle(self.up.normalize(), -yaw)
+            * Quaternion::from_axis_angle(self.right(), -pitch);
+        self.eye = self.target + rotation.rotate_vector(offset);
+    }
+
+    /// Moves the eye towards (`factor < 1`) or away from the target.
+    pub fn zoom(&mut self, factor: f32) {
+        let offset = (self.eye - self.target) * factor;
+        if offset.magnitude() > 0.05 {
+            self.eye = self.target + offset;
+        }
+    }
+
+    /// Moves eye and target together in the view plane.
+    pub fn pan(&mut self, right: f32, up: f32) {
+        let up_dir = self.right().cross(self.forward());
+        let delta = self.right() * right + up_dir * up;
+        self.eye += delta;
+        self.target += delta;
+    }
+
+    /// Applies an arcball rotation given in view space: the scene appears to
+    /// turn by `rotation`, so the camera turns the opposite way around the
+    /// target.
+    pub fn rotate_view(&mut self, rotation: Quaternion<f32>) {
+        let basis = Matrix3::from_cols(
+            self.right(), self.right().cross(self.forward()), -self.forward()
+        );
+        let world = Quaternion::from(basis) * rotation * Quaternion::from(basis).conjugate();
+        let inverse = world.conjugate();
+        self.eye = self.target + inverse.rotate_vector(self.eye - self.target);
+        self.up = inverse.rotate_vector(self.up);
+    }
+
+    /// Turns the view direction in place, keeping the distance to the target.
+    pub fn look(&mut self, yaw: Rad<f32>, pitch: Rad<f32>) {
+        let offset = self.target - self.eye;
+        let max_pitch = 0.49 * std::f32::consts::PI;
+        let current = offset.normalize().dot(self.up).clamp(-1.0, 1.0).asin();
+        let pitch = (current + pitch.0).clamp(-max_pitch, max_pitch) - current;
+        let rotation = Quaternion::from_axis_angle(self.up.normalize(), -yaw)
+            * Quaternion::from_axis_angle(self.right(), Rad(pitch));
+        self.target = self.eye + rotation.rotate_vector(offset);
+    }
+
+    /// Moves eye and target along the view direction, right and up vectors.
+    pub fn fly(&mut self, forward: f32, right: f32, up: f32) {
+        let delta = self.forward() * forward + self.right() * right + self.up.normalize() * up;
+        self.eye += delta;
+        self.target += delta;
+    }
+}
+
+/// Translates window events into camera movements.
+pub struct CameraController {
+    pub mode: CameraMode,
+    /// Radians per pixel of mouse movement.
+    pub rotate_speed: f32,
+    /// Scene units per second in fly mode.
+    pub fly_speed: f32,
+    cursor: Option<PhysicalPosition<f64>>,
+    left_pressed: bool,
+    middle_pressed: bool,
+    keys: HashSet<KeyCode>,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self::new(CameraMode::Orbit)
+    }
+}
+
+impl CameraController {
+    pub fn new(mode: CameraMode) -> Self {
+        Self {
+            mode,
+            rotate_speed: 0.01,
+            fly_speed: 2.0,
+            cursor: None,
+            left_pressed: false,
+            middle_pressed: false,
+            keys: HashSet::new(),
+        }
+    }
+
+    /// Handles one window event, returning `true` if it was consumed.
+    pub fn process_event(
+        &mut self, camera: &mut Camera, event: &WindowEvent, size: PhysicalSize<u32>
+    ) -> bool {
+        match event {
+            WindowEvent::MouseInput { state, button, .. } => {
+                let pressed = *state == ElementState::Pressed;
+                match button {
+                    MouseButton::Left => self.left_pressed = pressed,
+                    MouseButton::Middle => self.middle_pressed = pressed,
+                    _ => return false,
+                }
+                true
+            },
+            WindowEvent::CursorMoved { position, .. } => {
+                if let Some(last) = self.cursor {
+                    self.drag(camera, last, *position, size);
+                }
+                self.cursor = Some(*position);
+                self.left_pressed || self.middle_pressed
+            },
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor = None;
+                false
+            },
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(p) => p.y as f32 / 40.0,
+                };
+                match self.mode {
+                    CameraMode::Fly => self.fly_speed *= 1.1f32.powf(lines),
+                    _ => camera.zoom(0.9f32.powf(lines)),
+                }
+                true
+            },
+            WindowEvent::KeyboardInput {
+                event: KeyEvent { state, physical_key: PhysicalKey::Code(key), repeat, .. },
+                ..
+            } => {
+                let pressed = *state == ElementState::Pressed;
+                match key {
+                    KeyCode::KeyC => {
+                        if pressed && !repeat {
+                            self.mode = self.mode.next();
+                            println!("Camera mode: {:?}", self.mode);
+                        }
+                        true
+                    },
+                    KeyCode::KeyW | KeyCode::KeyA | KeyCode::KeyS | KeyCode::KeyD
+                    | KeyCode::KeyQ | KeyCode::KeyE => {
+                        if pressed {
+                            self.keys.insert(*key);
+                        } else {
+                            self.keys.remove(key);
+                        }
+                        self.mode == CameraMode::Fly
+                    },
+                    _ => false,
+                }
+            },
+            _ => false,
+        }
+    }
+
+    fn drag(
+        &self, camera: &mut Camera, from: PhysicalPosition<f64>, to: PhysicalPosition<f64>,
+        size: PhysicalSize<u32>
+    ) {
+        let dx = (to.x - from.x) as f32;
+        let dy = (to.y - from.y) as f32;
+        if self.middle_pressed && self.mode != CameraMode::Fly {
+            // one window height pans by about the distance to the target
+            let scale = (camera.eye - camera.target).magnitude() / size.height.max(1) as f32;
+            camera.pan(-dx * scale, dy * scale);
+        }
+        if !self.left_pressed {
+            return;
+        }
+        match self.mode {
+            CameraMode::Orbit =>
+                camera.orbit(Rad(dx * self.rotate_speed), Rad(dy * self.rotate_speed)),
+            CameraMode::Arcball => {
+                let p0 = arcball_point(from, size);
+                let p1 = arcball_point(to, size);
+                camera.rotate_view(Quaternion::from_arc(p0, p1, None));
+            },
+            CameraMode::Fly =>
+                camera.look(Rad(dx * self.rotate_speed), Rad(-dy * self.rotate_speed)),
+        }
+    }
+
+    /// Advances fly-mode movement by the time since the last frame.
+    pub fn update(&self, camera: &mut Camera, dt: Duration) {
+        if self.mode != CameraMode::Fly {
+            return;
+        }
+        let axis = |positive, negative| {
+            self.keys.contains(&positive) as i32 as f32
+                - self.keys.contains(&negative) as i32 as f32
+        };
+        let step = self.fly_speed * dt.as_secs_f32();
+        camera.fly(
+            step * axis(KeyCode::KeyW, KeyCode::KeyS),
+            step * axis(KeyCode::KeyD, KeyCode::KeyA),
+            step * axis(KeyCode::KeyE, KeyCode::KeyQ),
+        );
+    }
+}
+
+// Shoemake's arcball: maps a window position onto the unit sphere in view
+// space (x right, y up, z towards the viewer), or onto its silhouette when
+// outside.
+fn arcball_point(position: PhysicalPosition<f64>, size: PhysicalSize<u32>) -> Vector3<f32> {
+    let radius = 0.5 * size.width.min(size.height).max(1) as f32;
+    let x = (position.x as f32 - 0.5 * size.width as f32) / radius;
+    let y = (0.5 * size.height as f32 - position.y as f32) / radius;
+    let d2 = x * x + y * y;
+    if d2 <= 1.0 {
+        Vector3::new(x, y, (1.0 - d2).sqrt())
+    } else {
+        Vector3::new(x, y, 0.0) / d2.sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera() -> Camera {
+        Camera::new((3.0, 1.5, 3.0).into(), (0.0, 0.0, 0.0).into(), Vector3::unit_y())
+    }
+
+    fn assert_near(a: Point3<f32>, b: Point3<f32>) {
+        assert!(a.distance(b) < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn orbit_keeps_distance_and_clamps_pitch() {
+        let mut c = camera();
+        let distance = c.eye.distance(c.target);
+        c.orbit(Rad(1.0), Rad(0.3));
+        assert!((c.eye.distance(c.target) - distance).abs() < 1e-4);
+        c.orbit(Rad(0.0), Rad(10.0));
+        assert!(c.eye.y < distance && c.eye.y > 0.99 * distance);
+
+        // a full turn comes back
+        let mut c = camera();
+        c.orbit(Rad(2.0 * std::f32::consts::PI), Rad(0.0));
+        assert_near(c.eye, camera().eye);
+    }
+
+    #[test]
+    fn zoom_and_pan_move_eye_and_target() {
+        let mut c = camera();
+        c.zoom(0.5);
+        assert_near(c.eye, Point3::new(1.5, 0.75, 1.5));
+        c.pan(1.0, 0.0);
+        assert!((c.eye.distance(c.target) - camera().eye.distance(camera().target) * 0.5).abs() < 1e-4);
+        assert!(c.target.distance(Point3::origin()) > 0.99);
+    }
+
+    #[test]
+    fn arcball_drag_across_center_turns_around_target() {
+        let mut c = camera();
+        let size = PhysicalSize::new(200, 200);
+        // drag from the center to the right edge: a quarter turn
+        let q = Quaternion::from_arc(
+            arcball_point(PhysicalPosition::new(100.0, 100.0), size),
+            arcball_point(PhysicalPosition::new(200.0, 100.0), size),
+            None,
+        );
+        c.rotate_view(q);
+        let distance = camera().eye.distance(camera().target);
+        assert!((c.eye.distance(c.target) - distance).abs() < 1e-4);
+        let turned = (c.eye - c.target).normalize().dot((camera().eye - camera().target).normalize());
+        assert!(turned.abs() < 1e-4, "{}", turned);
+    }
+
+    #[test]
+    fn fly_moves_along_view_direction() {
+        let mut c = camera();
+        let mut controller = CameraController::new(CameraMode::Fly);
+        controller.keys.insert(KeyCode::KeyW);
+        controller.update(&mut c, Duration::from_secs(1));
+        let forward = (camera().target - camera().eye).normalize();
+        assert_near(c.eye, camera().eye + forward * controller.fly_speed);
+        assert_near(c.target, camera().target + forward * controller.fly_speed);
+    }
+}
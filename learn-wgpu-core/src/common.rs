@@ -0,0 +1,1167 @@
+use std::{iter, mem};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use bytemuck::{ Pod, Zeroable };
+use cgmath::{ Matrix4, SquareMatrix };
+use wgpu::util::DeviceExt;
+use winit::{
+    event::WindowEvent,
+    keyboard::KeyCode,
+    window::Window
+};
+
+use crate::{animation, app, camera, depth, lights, material, mesh, msaa, readback, scene_graph, screenshot, shader, shadow, texture, transforms, uniforms};
+use crate::lights::{LightAnimation, LightSource};
+use crate::material::{Material, SurfaceMaterial};
+use crate::shadow::ShadowConfig;
+use crate::config::{self, InitError};
+use crate::instance::{Instance, InstanceAnimation, InstanceRaw};
+use crate::depth::DepthConfig;
+use crate::scene_graph::{GraphAnimation, NodeId, SceneGraph};
+
+const IS_PERSPECTIVE : bool = true;
+/// Source of the baked-in shader, read instead when hot-reloading.
+const SHADER_PATH : &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/shader.wgsl");
+/// Source of the baked-in `common.wgsl`, watched along with the shader.
+const COMMON_SHADER_PATH : &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/common.wgsl");
+
+/// A material whose ambient, diffuse and specular colors are `c`, `c` and
+/// `sc` scaled by `ai`, `di` and `si`, with shininess `ss`.
+pub fn material(c: [f32; 3], sc: [f32; 3], ai: f32, di: f32, si: f32, ss: f32) -> Material {
+    Material {
+        ambient: c.map(|c| c * ai),
+        diffuse: c.map(|c| c * di),
+        specular: sc.map(|c| c * si),
+        shininess: ss,
+        diffuse_texture: None,
+        specular_texture: None,
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct Vertex {
+    pub position: [f32; 4],
+    pub normal: [f32; 4],
+}
+
+impl Vertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x4];
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// Vertex with texture coordinates, the format [`State`] draws.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct TexturedVertex {
+    pub position: [f32; 4],
+    pub normal: [f32; 4],
+    pub uv: [f32; 2],
+}
+
+impl TexturedVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x4, 2 => Float32x2];
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<TexturedVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+impl From<Vertex> for TexturedVertex {
+    fn from(v: Vertex) -> Self {
+        Self { position: v.position, normal: v.normal, uv: [0.0, 0.0] }
+    }
+}
+
+/// A mesh placed in the scene with its own model matrix and material.
+pub struct SceneObject {
+    pub vertex_data: Vec<TexturedVertex>,
+    /// Drawn with `draw_indexed` when set, otherwise `vertex_data` is a
+    /// triangle list.
+    pub indices: Option<mesh::Indices>,
+    pub model_mat: Matrix4<f32>,
+    pub material: SurfaceMaterial,
+}
+
+impl SceneObject {
+    /// Wraps a single triangle list at the origin.
+    pub fn new(vertex_data: &[Vertex], material: impl Into<SurfaceMaterial>) -> Self {
+        Self {
+            vertex_data: vertex_data.iter().map(|&v| v.into()).collect(),
+            indices: None,
+            model_mat: Matrix4::identity(),
+            material: material.into(),
+        }
+    }
+
+    /// Wraps a single triangle list with texture coordinates at the origin.
+    pub fn textured(
+        vertex_data: &[TexturedVertex], material: impl Into<SurfaceMaterial>
+    ) -> Self {
+        Self {
+            vertex_data: vertex_data.to_vec(),
+            indices: None,
+            model_mat: Matrix4::identity(),
+            material: material.into(),
+        }
+    }
+
+    /// Wraps a single indexed mesh at the origin.
+    pub fn indexed(mesh: &mesh::IndexedMesh, material: impl Into<SurfaceMaterial>) -> Self {
+        Self {
+            vertex_data: mesh.vertices.iter().map(|&v| v.into()).collect(),
+            indices: Some(mesh.indices.clone()),
+            model_mat: Matrix4::identity(),
+            material: material.into(),
+        }
+    }
+}
+
+// GPU resources of a scene_graph::Mesh
+struct MeshBuffers {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: Option<(wgpu::Buffer, wgpu::IndexFormat)>,
+    // indices if indexed, vertices otherwise
+    num_elements: u32,
+}
+
+impl MeshBuffers {
+    fn new(device: &wgpu::Device, mesh: &scene_graph::Mesh) -> Self {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffer"),
+            contents: bytemuck::cast_slice(&mesh.vertex_data),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = mesh.indices.as_ref().map(|indices| {
+            let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Index Buffer"),
+                contents: &indices.as_bytes(),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+            (buffer, indices.format())
+        });
+        let num_elements = match &mesh.indices {
+            Some(indices) => indices.len(),
+            None => mesh.vertex_data.len(),
+        } as u32;
+        Self { vertex_buffer, index_buffer, num_elements }
+    }
+
+    // with the bind groups already set
+    fn draw(&self, rpass: &mut wgpu::RenderPass, instance_buffer: &wgpu::Buffer, instances: u32) {
+        rpass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        rpass.set_vertex_buffer(1, instance_buffer.slice(..));
+        match &self.index_buffer {
+            Some((buffer, format)) => {
+                rpass.set_index_buffer(buffer.slice(..), *format);
+                rpass.draw_indexed(0..self.num_elements, 0, 0..instances);
+            },
+            None => rpass.draw(0..self.num_elements, 0..instances),
+        }
+    }
+}
+
+// GPU resources of a SurfaceMaterial
+struct MaterialBuffers {
+    bind_group: wgpu::BindGroup,
+    // drawn with the PBR pipeline instead of the Blinn-Phong one
+    pbr: bool,
+}
+
+// a graph node with a mesh, whose uniforms are at its index times the
+// stride into the object uniform buffer
+struct ObjectBuffers {
+    node: NodeId,
+    // without instances a single default one is drawn
+    instances: Vec<Instance>,
+    instance_buffer: wgpu::Buffer,
+    instance_animation: Option<InstanceAnimation>,
+    // instances changed since they were last uploaded
+    instances_dirty: bool,
+}
+
+impl ObjectBuffers {
+    fn new(device: &wgpu::Device, node: NodeId) -> Self {
+        Self {
+            node,
+            instances: Vec::new(),
+            instance_buffer: create_instance_buffer(
+                device, bytemuck::bytes_of(&Instance::default().to_raw())
+            ),
+            instance_animation: None,
+            instances_dirty: false,
+        }
+    }
+
+    fn instance_count(&self) -> u32 {
+        self.instances.len().max(1) as u32
+    }
+
+    // uploads the instances, growing the buffer if they no longer fit
+    fn write_instances(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let raw: Vec<InstanceRaw> = if self.instances.is_empty() {
+            vec![Instance::default().to_raw()]
+        } else {
+            self.instances.iter().map(Instance::to_raw).collect()
+        };
+        let bytes: &[u8] = bytemuck::cast_slice(&raw);
+        if bytes.len() as u64 > self.instance_buffer.size() {
+            self.instance_buffer = create_instance_buffer(device, bytes);
+        } else {
+            queue.write_buffer(&self.instance_buffer, 0, bytes);
+        }
+        self.instances_dirty = false;
+    }
+}
+
+// watchers of the lit shader and of the common.wgsl in front of it, with
+// the sources they last read
+struct ShaderReload {
+    common: shader::ShaderWatcher,
+    lit: shader::ShaderWatcher,
+    common_source: String,
+    lit_source: String,
+}
+
+fn create_instance_buffer(device: &wgpu::Device, contents: &[u8]) -> wgpu::Buffer {
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Instance Buffer"),
+        contents,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+    })
+}
+
+// the object uniforms are bound with a dynamic offset per object, the
+// fragment uniforms are shared
+fn create_uniform_bind_group(
+    device: &wgpu::Device, layout: &wgpu::BindGroupLayout, object_uniform_buffer: &wgpu::Buffer,
+    fragment_uniform_buffer: &wgpu::Buffer
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: object_uniform_buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(mem::size_of::<uniforms::VertexUniforms>() as u64),
+                }),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: fragment_uniform_buffer.as_entire_binding(),
+            },
+        ],
+        label: Some("Uniform Bind Group"),
+    })
+}
+
+fn create_object_uniform_buffer(device: &wgpu::Device, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Object Uniform Buffer"),
+        size,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+pub struct State<'a> {
+    init: transforms::InitWgpu<'a>,
+    pipeline: wgpu::RenderPipeline,
+    pbr_pipeline: wgpu::RenderPipeline,
+    pipeline_layout: wgpu::PipelineLayout,
+    // kept to rebuild the pipelines for another depth format or sample count
+    shader: wgpu::ShaderModule,
+    pbr_shader: wgpu::ShaderModule,
+    depth: depth::DepthBuffer,
+    sample_count: u32,
+    // drawn into and resolved into the frame when multisampling
+    msaa_target: Option<msaa::ColorBuffer>,
+    uniform_bind_group_layout: wgpu::BindGroupLayout,
+    shadow_bind_group_layout: wgpu::BindGroupLayout,
+    shadow_map: shadow::ShadowMap,
+    shader_reload: Option<ShaderReload>,
+    material_bind_group_layout: wgpu::BindGroupLayout,
+    // bound in place of missing material textures
+    white_texture: texture::Texture,
+    graph: SceneGraph,
+    graph_animation: Option<GraphAnimation>,
+    // uploaded from the graph, indexed by MeshId and MaterialId
+    meshes: Vec<MeshBuffers>,
+    materials: Vec<MaterialBuffers>,
+    // the nodes with a mesh, in graph order
+    objects: Vec<ObjectBuffers>,
+    object_uniform_buffer: wgpu::Buffer,
+    // VertexUniforms rounded up to the dynamic offset alignment
+    object_uniform_stride: u64,
+    uniform_bind_group: wgpu::BindGroup,
+    fragment_uniform_buffer: wgpu::Buffer,
+    frag_uniforms: uniforms::FragUniforms,
+    lights: Vec<LightSource>,
+    light_animation: Option<LightAnimation>,
+    camera: camera::Camera,
+    camera_controller: camera::CameraController,
+    view_mat: Matrix4<f32>,
+    project_mat: Matrix4<f32>,
+    spin: bool,
+    clock: animation::Clock,
+    clips: Vec<animation::Clip>,
+    // time passed to the previous update, to derive frame times
+    last_update: std::time::Duration,
+    window: Option<Arc<Window>>,
+    screenshot: screenshot::Pending,
+}
+
+impl<'a> State<'a> {
+    pub async fn new(
+        window: Arc<Window>, vertex_data: &[Vertex], material: impl Into<SurfaceMaterial>
+    ) -> Result<Self, InitError> {
+        Self::new_scene(window, &[SceneObject::new(vertex_data, material)]).await
+    }
+
+    /// Creates a state that renders into an offscreen texture of the given
+    /// size instead of a window surface.
+    pub async fn new_offscreen(
+        width: u32, height: u32, vertex_data: &[Vertex], material: impl Into<SurfaceMaterial>
+    ) -> Self {
+        Self::new_offscreen_scene(
+            width, height, &[SceneObject::new(vertex_data, material)]
+        ).await
+    }
+
+    /// Creates a state that draws several objects, each with its own model
+    /// matrix and material.
+    pub async fn new_scene(
+        window: Arc<Window>, objects: &[SceneObject]
+    ) -> Result<Self, InitError> {
+        Self::new_graph(window, SceneGraph::from_objects(objects)).await
+    }
+
+    pub async fn new_offscreen_scene(
+        width: u32, height: u32, objects: &[SceneObject]
+    ) -> Self {
+        Self::new_offscreen_graph(width, height, SceneGraph::from_objects(objects)).await
+    }
+
+    /// Creates a state that draws every node of `graph` that has a mesh,
+    /// placed by its world matrix.
+    pub async fn new_graph(window: Arc<Window>, graph: SceneGraph) -> Result<Self, InitError> {
+        let init = transforms::InitWgpu::init_wgpu(window.clone()).await?;
+        Ok(Self::with_init(init, Some(window), graph))
+    }
+
+    pub async fn new_offscreen_graph(width: u32, height: u32, graph: SceneGraph) -> Self {
+        let init = transforms::InitWgpu::init_offscreen(width, height).await;
+        Self::with_init(init, None, graph)
+    }
+
+    fn with_init(
+        init: transforms::InitWgpu<'a>, window: Option<Arc<Window>>, graph: SceneGraph
+    ) -> Self {
+        // Load the shaders from disk
+        let shader = init.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(include_str!("common.wgsl"), include_str!("shader.wgsl")).into()
+            ),
+        });
+
+        // uniform data
+        let camera_position = (3.0, 1.5, 3.0).into();
+        let look_direction = (0.0, 0.0, 0.0).into();
+        let up_direction = cgmath::Vector3::unit_y();
+        let camera = camera::Camera::new(camera_position, look_direction, up_direction);
+
+        let (view_mat, project_mat, _) =
+            transforms::create_view_projection(
+                camera_position, look_direction, up_direction,
+                init.config.width as f32 / init.config.height as f32,
+                IS_PERSPECTIVE
+            );
+
+        // the light starts where the camera does, but stays there
+        let lights = lights::default_lights(camera_position.into());
+        let frag_uniforms = uniforms::FragUniforms::new(camera_position.into(), &lights);
+        let fragment_uniform_buffer = init.device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Fragment Uniform Buffer"),
+                contents: bytemuck::bytes_of(&frag_uniforms),
+                usage: wgpu::BufferUsages::UNIFORM
+                     | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        let uniform_bind_group_layout = init.device.create_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: true,
+                            min_binding_size: wgpu::BufferSize::new(
+                                mem::size_of::<uniforms::VertexUniforms>() as u64
+                            ),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+                label: Some("Uniform Bind Group Layout"),
+            }
+        );
+
+        let material_bind_group_layout = material::bind_group_layout(&init.device);
+        let white_texture = texture::Texture::white(&init.device, &init.queue);
+        let shadow_bind_group_layout = shadow::ShadowMap::sampling_layout(&init.device);
+        let shadow_map = shadow::ShadowMap::new(
+            &init.device, &uniform_bind_group_layout, &shadow_bind_group_layout, None
+        );
+
+        let pipeline_layout = init.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Render Pipeline Layout"),
+            bind_group_layouts: &[
+                &uniform_bind_group_layout, &material_bind_group_layout, &shadow_bind_group_layout
+            ],
+            immediate_size: 0,
+        });
+
+        let depth_config = DepthConfig::default();
+        let depth = depth::DepthBuffer::new(
+            &init.device, init.config.width, init.config.height, depth_config
+        );
+        let pipeline = create_pipeline(
+            &init.device, &pipeline_layout, &shader, init.config.format, &depth_config, 1, &[]
+        );
+        let pbr_shader = init.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("PBR Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                concat!(include_str!("common.wgsl"), include_str!("pbr.wgsl")).into()
+            ),
+        });
+        let pbr_pipeline = create_pbr_pipeline(
+            &init.device, &pipeline_layout, &pbr_shader, init.config.format, &depth_config, 1
+        );
+
+        let object_uniform_stride = wgpu::util::align_to(
+            mem::size_of::<uniforms::VertexUniforms>() as u64,
+            init.device.limits().min_uniform_buffer_offset_alignment as u64
+        );
+        let object_uniform_buffer = create_object_uniform_buffer(
+            &init.device, object_uniform_stride
+        );
+        let uniform_bind_group = create_uniform_bind_group(
+            &init.device, &uniform_bind_group_layout, &object_uniform_buffer,
+            &fragment_uniform_buffer
+        );
+
+        let mut state = Self {
+            init,
+            pipeline,
+            pbr_pipeline,
+            pipeline_layout,
+            shader,
+            pbr_shader,
+            depth,
+            sample_count: 1,
+            msaa_target: None,
+            uniform_bind_group_layout,
+            shadow_bind_group_layout,
+            shadow_map,
+            shader_reload: None,
+            material_bind_group_layout,
+            white_texture,
+            graph,
+            graph_animation: None,
+            meshes: Vec::new(),
+            materials: Vec::new(),
+            objects: Vec::new(),
+            object_uniform_buffer,
+            object_uniform_stride,
+            uniform_bind_group,
+            fragment_uniform_buffer,
+            frag_uniforms,
+            lights,
+            light_animation: None,
+            camera,
+            camera_controller: camera::CameraController::default(),
+            view_mat,
+            project_mat,
+            spin: true,
+            clock: animation::Clock::default(),
+            clips: Vec::new(),
+            last_update: std::time::Duration::ZERO,
+            window,
+            screenshot: screenshot::Pending::default(),
+        };
+        state.sync_graph();
+        state
+    }
+
+    /// Loads the shader from `path`, and the `common.wgsl` in front of it
+    /// from the source tree, on the next [`State::poll_shader`] and again
+    /// whenever either file changes.
+    pub fn watch_shader(&mut self, path: impl Into<PathBuf>) {
+        self.shader_reload = Some(ShaderReload {
+            common: shader::ShaderWatcher::new(COMMON_SHADER_PATH),
+            lit: shader::ShaderWatcher::new(path),
+            common_source: shader::COMMON_WGSL.to_string(),
+            lit_source: include_str!("shader.wgsl").to_string(),
+        });
+    }
+
+    /// Rebuilds the lit pipelines if the watched shaders changed. Errors are
+    /// printed and the previous pipelines are kept.
+    pub fn poll_shader(&mut self) {
+        let Some(reload) = &mut self.shader_reload else { return };
+        let mut changed = Vec::new();
+        for (watcher, source) in [
+            (&mut reload.common, &mut reload.common_source),
+            (&mut reload.lit, &mut reload.lit_source),
+        ] {
+            let path = watcher.path().display().to_string();
+            match watcher.poll() {
+                None => {},
+                Some(Ok(new_source)) => {
+                    *source = new_source;
+                    changed.push(path);
+                },
+                Some(Err(e)) => eprintln!("Keeping the previous shader:\nfailed to read {}: {}", path, e),
+            }
+        }
+        if changed.is_empty() {
+            return;
+        }
+        let path = reload.lit.path().display().to_string();
+        let (common, source) = (reload.common_source.clone(), reload.lit_source.clone());
+        match self.reload_shader(&common, &source, &path) {
+            Ok(()) => eprintln!("Reloaded {}", changed.join(", ")),
+            Err(e) => eprintln!("Keeping the previous shader:\n{}", e),
+        }
+    }
+
+    /// Validates `source` and `pbr.wgsl`, each behind `common`, and replaces
+    /// both lit pipelines with ones built from them. Nothing is replaced if
+    /// either fails.
+    pub fn reload_shader(&mut self, common: &str, source: &str, path: &str) -> Result<(), String> {
+        let source = [common, source].concat();
+        let pbr_source = [common, include_str!("pbr.wgsl")].concat();
+        shader::validate_wgsl(&source, path)?;
+        shader::validate_wgsl(&pbr_source, "pbr.wgsl")?;
+        // naga does not check the shader against the pipeline layout and
+        // vertex buffers, so catch what wgpu reports
+        let device = &self.init.device;
+        let scope = device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(path),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let pbr_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("PBR Shader"),
+            source: wgpu::ShaderSource::Wgsl(pbr_source.into()),
+        });
+        let pipeline = create_pipeline(
+            device, &self.pipeline_layout, &module, self.init.config.format,
+            self.depth.config(), self.sample_count, &[]
+        );
+        let pbr_pipeline = create_pbr_pipeline(
+            device, &self.pipeline_layout, &pbr_module, self.init.config.format,
+            self.depth.config(), self.sample_count
+        );
+        if let Some(error) = pollster::block_on(scope.pop()) {
+            return Err(error.to_string());
+        }
+        self.pipeline = pipeline;
+        self.shader = module;
+        self.pbr_pipeline = pbr_pipeline;
+        self.pbr_shader = pbr_module;
+        Ok(())
+    }
+
+    /// Replaces the depth buffer and rebuilds the pipelines for its format
+    /// and depth test. Multisampling is turned off if the format does not
+    /// support the current sample count.
+    pub fn set_depth(&mut self, config: DepthConfig) {
+        // rebuilt below, after checking the sample count against the format
+        self.depth = depth::DepthBuffer::new(&self.init.device, 1, 1, config);
+        if !self.supported_sample_counts().contains(&self.sample_count) {
+            self.sample_count = 1;
+        }
+        self.rebuild_targets();
+    }
+
+    pub fn depth(&self) -> &DepthConfig {
+        self.depth.config()
+    }
+
+    /// Sample counts of [`msaa::SAMPLE_COUNTS`] that the surface and depth
+    /// formats support on this adapter.
+    pub fn supported_sample_counts(&self) -> Vec<u32> {
+        msaa::supported_sample_counts(
+            &self.init.adapter, &self.init.device, self.init.config.format,
+            self.depth.config().format
+        )
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// Draws with `sample_count` samples per pixel, 1 turning
+    /// multisampling off. The count is checked against
+    /// [`State::supported_sample_counts`].
+    pub fn set_sample_count(
+        &mut self, sample_count: u32
+    ) -> Result<(), msaa::UnsupportedSampleCount> {
+        let supported = self.supported_sample_counts();
+        if !supported.contains(&sample_count) {
+            return Err(msaa::UnsupportedSampleCount { requested: sample_count, supported });
+        }
+        self.sample_count = sample_count;
+        self.rebuild_targets();
+        Ok(())
+    }
+
+    /// Switches to the next supported sample count, wrapping around to 1,
+    /// and returns it.
+    pub fn cycle_sample_count(&mut self) -> u32 {
+        let supported = self.supported_sample_counts();
+        let next = supported
+            .iter()
+            .copied()
+            .find(|&count| count > self.sample_count)
+            .unwrap_or(1);
+        self.set_sample_count(next).expect("sample count is supported");
+        next
+    }
+
+    // recreates the attachments and pipelines for the sample count and
+    // depth config
+    fn rebuild_targets(&mut self) {
+        let device = &self.init.device;
+        let (width, height) = (self.init.config.width, self.init.config.height);
+        let format = self.init.config.format;
+        let depth_config = *self.depth.config();
+        self.depth = depth::DepthBuffer::multisampled(
+            device, width, height, depth_config, self.sample_count
+        );
+        self.msaa_target = (self.sample_count > 1).then(|| {
+            msaa::ColorBuffer::new(device, width, height, format, self.sample_count)
+        });
+        self.pipeline = create_pipeline(
+            device, &self.pipeline_layout, &self.shader, format, &depth_config,
+            self.sample_count, &[]
+        );
+        self.pbr_pipeline = create_pbr_pipeline(
+            device, &self.pipeline_layout, &self.pbr_shader, format, &depth_config,
+            self.sample_count
+        );
+    }
+
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            // Recreate the surface and attachments with the new size
+            self.init.resize(new_size);
+            self.depth.resize(&self.init.device, new_size.width, new_size.height);
+            if let Some(target) = &mut self.msaa_target {
+                target.resize(&self.init.device, new_size.width, new_size.height);
+            }
+
+            self.project_mat = transforms::create_projection(
+                new_size.width as f32 / new_size.height as f32, IS_PERSPECTIVE
+            );
+        }
+    }
+
+    pub fn input(&mut self, event: &WindowEvent) -> bool {
+        self.camera_controller.process_event(&mut self.camera, event, self.init.size)
+            || self.clock.process_event(event)
+    }
+
+    /// The camera is read back on the next [`State::update`].
+    pub fn camera_mut(&mut self) -> &mut camera::Camera {
+        &mut self.camera
+    }
+
+    /// Replaces the lights. Panics if there are more than
+    /// [`lights::MAX_LIGHTS`].
+    pub fn set_lights(&mut self, lights: &[LightSource]) {
+        self.frag_uniforms.set_lights(lights);
+        self.lights = lights.to_vec();
+    }
+
+    /// The lights are read back on the next [`State::update`].
+    pub fn lights_mut(&mut self) -> &mut [LightSource] {
+        &mut self.lights
+    }
+
+    /// Moves the lights on every [`State::update`], independently of the
+    /// camera and of the spinning scene.
+    pub fn set_light_animation(&mut self, animation: LightAnimation) {
+        self.light_animation = Some(animation);
+    }
+
+    /// Whether the scene spins around the origin, which it does by
+    /// default.
+    pub fn set_spin(&mut self, spin: bool) {
+        self.spin = spin;
+    }
+
+    /// Casts shadows from the first [`shadow::MAX_SHADOWS`] directional and
+    /// spot lights, or from none without a config, which is the default.
+    pub fn set_shadows(&mut self, config: Option<ShadowConfig>) {
+        self.shadow_map = shadow::ShadowMap::new(
+            &self.init.device, &self.uniform_bind_group_layout,
+            &self.shadow_bind_group_layout, config
+        );
+    }
+
+    pub fn shadows(&self) -> Option<&ShadowConfig> {
+        self.shadow_map.config()
+    }
+
+    /// Replaces the instances of the `object`th object, counting the graph's
+    /// nodes with a mesh in order. An object without instances is drawn
+    /// once, as if with [`Instance::default`].
+    pub fn set_instances(&mut self, object: usize, instances: &[Instance]) {
+        let object = &mut self.objects[object];
+        object.instances = instances.to_vec();
+        object.instances_dirty = true;
+    }
+
+    /// Adds instances to the `object`th object and returns their indices.
+    pub fn add_instances(&mut self, object: usize, instances: &[Instance]) -> Range<usize> {
+        let object = &mut self.objects[object];
+        let start = object.instances.len();
+        object.instances.extend_from_slice(instances);
+        object.instances_dirty = true;
+        start..object.instances.len()
+    }
+
+    /// The instances are uploaded on the next [`State::update`].
+    pub fn instances_mut(&mut self, object: usize) -> &mut [Instance] {
+        let object = &mut self.objects[object];
+        object.instances_dirty = true;
+        &mut object.instances
+    }
+
+    /// Moves the instances of the `object`th object on every
+    /// [`State::update`]. They still spin with the scene.
+    pub fn set_instance_animation(&mut self, object: usize, animation: InstanceAnimation) {
+        self.objects[object].instance_animation = Some(animation);
+    }
+
+    pub fn graph(&self) -> &SceneGraph {
+        &self.graph
+    }
+
+    /// Nodes, meshes and materials added to the graph are uploaded and
+    /// transforms applied on the next [`State::update`].
+    pub fn graph_mut(&mut self) -> &mut SceneGraph {
+        &mut self.graph
+    }
+
+    /// Moves the graph's nodes on every [`State::update`]. They still spin
+    /// with the scene.
+    pub fn set_graph_animation(&mut self, animation: GraphAnimation) {
+        self.graph_animation = Some(animation);
+    }
+
+    /// Plays `clip` on every [`State::update`] at the clock's time, after
+    /// the animation callbacks.
+    pub fn add_clip(&mut self, clip: animation::Clip) {
+        self.clips.push(clip);
+    }
+
+    /// The time all animations, including the spin, are evaluated at.
+    pub fn clock(&self) -> &animation::Clock {
+        &self.clock
+    }
+
+    pub fn clock_mut(&mut self) -> &mut animation::Clock {
+        &mut self.clock
+    }
+
+    // uploads the meshes and materials added to the graph, keeps an object
+    // per node with a mesh and grows the object uniforms to fit them
+    fn sync_graph(&mut self) {
+        let device = &self.init.device;
+        for mesh in &self.graph.meshes()[self.meshes.len()..] {
+            self.meshes.push(MeshBuffers::new(device, mesh));
+        }
+        for material in &self.graph.materials()[self.materials.len()..] {
+            self.materials.push(MaterialBuffers {
+                bind_group: material.bind_group(
+                    device, &self.init.queue, &self.material_bind_group_layout,
+                    &self.white_texture
+                ),
+                pbr: matches!(material, SurfaceMaterial::Pbr(_)),
+            });
+        }
+
+        let nodes: Vec<NodeId> = self.graph
+            .nodes()
+            .filter(|(_, node)| node.mesh().is_some())
+            .map(|(id, _)| id)
+            .collect();
+        if !nodes.iter().eq(self.objects.iter().map(|object| &object.node)) {
+            // objects that still draw keep their instances
+            let mut previous: HashMap<NodeId, ObjectBuffers> =
+                self.objects.drain(..).map(|object| (object.node, object)).collect();
+            self.objects = nodes
+                .into_iter()
+                .map(|node| {
+                    previous.remove(&node).unwrap_or_else(|| ObjectBuffers::new(device, node))
+                })
+                .collect();
+        }
+
+        let size = self.object_uniform_stride * self.objects.len().max(1) as u64;
+        if size > self.object_uniform_buffer.size() {
+            self.object_uniform_buffer = create_object_uniform_buffer(device, size);
+            self.uniform_bind_group = create_uniform_bind_group(
+                device, &self.uniform_bind_group_layout, &self.object_uniform_buffer,
+                &self.fragment_uniform_buffer
+            );
+        }
+    }
+
+    // draws the `index`th object with its uniforms bound at group 0
+    fn draw_object(&self, rpass: &mut wgpu::RenderPass, index: usize) {
+        let object = &self.objects[index];
+        let (mesh, _) = self.graph.node(object.node).mesh().expect("objects have a mesh");
+        let offset = index as u64 * self.object_uniform_stride;
+        rpass.set_bind_group(0, &self.uniform_bind_group, &[offset as wgpu::DynamicOffset]);
+        self.meshes[mesh.0].draw(rpass, &object.instance_buffer, object.instance_count());
+    }
+
+    /// `dt` is the time since the start; the camera moves by the frame
+    /// times it implies, the animations by the [`State::clock`].
+    pub fn update(&mut self, dt: std::time::Duration) {
+        // move the camera
+        self.camera_controller.update(&mut self.camera, dt.saturating_sub(self.last_update));
+        // an earlier time than the last winds the animations back
+        self.clock.advance(dt.as_secs_f32() - self.last_update.as_secs_f32());
+        self.last_update = dt;
+        self.view_mat = self.camera.view_mat();
+        self.frag_uniforms.eye_position = self.camera.eye.to_homogeneous().into();
+
+        let (time, elapsed) = (self.clock.time(), self.clock.elapsed());
+        if let Some(animation) = &mut self.light_animation {
+            animation(&mut self.lights, elapsed);
+        }
+        if let Some(animation) = &mut self.graph_animation {
+            animation(&mut self.graph, elapsed);
+        }
+        for clip in &self.clips {
+            clip.apply(time, &mut self.graph, &mut self.lights);
+        }
+        self.frag_uniforms.set_lights(&self.lights);
+        self.shadow_map.update(&self.init.queue, &self.lights, &mut self.frag_uniforms.lights);
+        self.init.queue.write_buffer(
+            &self.fragment_uniform_buffer, 0, bytemuck::bytes_of(&self.frag_uniforms)
+        );
+
+        self.graph.update_world_matrices();
+        self.sync_graph();
+
+        for (index, object) in self.objects.iter_mut().enumerate() {
+            if let Some(animation) = &mut object.instance_animation {
+                animation(&mut object.instances, elapsed);
+                object.instances_dirty = true;
+            }
+            for clip in &self.clips {
+                if clip.apply_instances(time, index, &mut object.instances) {
+                    object.instances_dirty = true;
+                }
+            }
+            if object.instances_dirty {
+                object.write_instances(&self.init.device, &self.init.queue);
+            }
+        }
+
+        // update uniform buffer
+        let animation_mat = if self.spin {
+            transforms::Transform::from_euler(
+                [time.sin(), time.cos(), 0.0], transforms::EulerOrder::XYZ
+            ).to_matrix()
+        } else {
+            Matrix4::identity()
+        };
+        let project_mat = if self.depth.config().reversed_z {
+            transforms::reverse_z(self.project_mat)
+        } else {
+            self.project_mat
+        };
+        let view_project_mat = project_mat * self.view_mat;
+
+        // all objects in one write, each at its own offset
+        let stride = self.object_uniform_stride as usize;
+        let mut object_uniforms = vec![0u8; stride * self.objects.len()];
+        for (object, chunk) in self.objects.iter().zip(object_uniforms.chunks_mut(stride)) {
+            // the whole scene spins around the origin, unless disabled
+            let vertex_uniforms = uniforms::VertexUniforms::new(
+                animation_mat * self.graph.node(object.node).world_matrix(), view_project_mat
+            );
+            let bytes = bytemuck::bytes_of(&vertex_uniforms);
+            chunk[..bytes.len()].copy_from_slice(bytes);
+        }
+        if !object_uniforms.is_empty() {
+            self.init.queue.write_buffer(&self.object_uniform_buffer, 0, &object_uniforms);
+        }
+    }
+
+    pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+        let frame = self.init.get_current_frame()?;
+        let view = frame
+            .texture()
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self
+            .init
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+
+        self.shadow_map.render(&mut encoder, |rpass| {
+            for index in 0..self.objects.len() {
+                self.draw_object(rpass, index);
+            }
+        });
+
+        // with multisampling only the resolved frame is kept
+        let (target_view, resolve_target, store) = match &self.msaa_target {
+            Some(target) => (target.view(), Some(&view), wgpu::StoreOp::Discard),
+            None => (&view, None, wgpu::StoreOp::Store),
+        };
+
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target_view,
+                    resolve_target,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(
+                            wgpu::Color {
+                                r: 0.0,
+                                g: 0.0,
+                                b: 0.0,
+                                a: 1.0
+                            }),
+                        store,
+                    },
+                    depth_slice: None,
+                })],
+                depth_stencil_attachment: Some(self.depth.attachment()),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+                multiview_mask: None,
+            });
+            rpass.set_bind_group(2, self.shadow_map.bind_group(), &[]);
+            let (mut pbr, mut bound_material) = (None, None);
+            for (index, object) in self.objects.iter().enumerate() {
+                let node = self.graph.node(object.node);
+                let (_, material_id) = node.mesh().expect("objects have a mesh");
+                let material = &self.materials[material_id.0];
+                if pbr != Some(material.pbr) {
+                    rpass.set_pipeline(if material.pbr { &self.pbr_pipeline } else { &self.pipeline });
+                    pbr = Some(material.pbr);
+                }
+                // nodes sharing a material keep its bind group
+                if bound_material != Some(material_id) {
+                    rpass.set_bind_group(1, &material.bind_group, &[]);
+                    bound_material = Some(material_id);
+                }
+                self.draw_object(&mut rpass, index);
+            }
+        }
+        self.init.queue.submit(iter::once(encoder.finish()));
+        self.screenshot.save_requested(
+            &self.init.device, &self.init.queue, frame.texture(), &self.init.config
+        );
+        frame.present();
+
+        Ok(())
+    }
+
+    /// Renders a frame through [`State::render`] and reads it back as
+    /// tightly packed RGBA8 bytes. Only available for offscreen states.
+    pub fn render_to_rgba(&mut self) -> Result<Vec<u8>, wgpu::SurfaceError> {
+        self.render()?;
+        let target = self.init.target.as_ref()
+            .expect("render_to_rgba requires an offscreen State");
+        Ok(readback::read_texture(&self.init.device, &self.init.queue, target))
+    }
+}
+
+impl app::App for State<'_> {
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        State::input(self, event)
+    }
+
+    fn update(&mut self, dt: std::time::Duration) {
+        State::update(self, dt);
+    }
+
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        State::render(self)
+    }
+
+    fn resize(&mut self, size: winit::dpi::PhysicalSize<u32>) {
+        State::resize(self, size);
+    }
+
+    fn size(&self) -> winit::dpi::PhysicalSize<u32> {
+        self.init.size
+    }
+
+    fn screenshot(&mut self) -> &mut screenshot::Pending {
+        &mut self.screenshot
+    }
+
+    fn key_pressed(&mut self, key: KeyCode) {
+        if key == KeyCode::KeyM {
+            println!("MSAA: {}x", self.cycle_sample_count());
+        }
+    }
+
+    fn begin_frame(&mut self) {
+        self.poll_shader();
+    }
+}
+
+// `constants` sets the shader's pipeline-overridable constants
+fn create_pipeline(
+    device: &wgpu::Device, layout: &wgpu::PipelineLayout, shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat, depth: &DepthConfig, sample_count: u32,
+    constants: &[(&str, f64)]
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: None,
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            buffers: &[TexturedVertex::desc(), InstanceRaw::desc()],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent::REPLACE,
+                    alpha: wgpu::BlendComponent::REPLACE,
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions {
+                constants,
+                ..Default::default()
+            },
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            ..Default::default()
+        },
+        depth_stencil: Some(depth.depth_stencil_state()),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
+        cache: None,
+        multiview_mask: None,
+    })
+}
+
+fn create_pbr_pipeline(
+    device: &wgpu::Device, layout: &wgpu::PipelineLayout, shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat, depth: &DepthConfig, sample_count: u32
+) -> wgpu::RenderPipeline {
+    // the shader encodes to sRGB itself unless the target does
+    let srgb_target = if format.is_srgb() { 1.0 } else { 0.0 };
+    create_pipeline(
+        device, layout, shader, format, depth, sample_count, &[("SRGB_TARGET", srgb_target)]
+    )
+}
+
+pub fn run(vertex_data: &[Vertex], material: impl Into<SurfaceMaterial>, title: &str) {
+    run_scene(&[SceneObject::new(vertex_data, material)], title);
+}
+
+pub fn run_scene(objects: &[SceneObject], title: &str) {
+    run_scene_with_lights(objects, None, None, title);
+}
+
+/// Like [`run_scene`], with the given lights instead of the default white
+/// one, optionally animated.
+pub fn run_scene_with_lights(
+    objects: &[SceneObject], lights: Option<&[LightSource]>,
+    light_animation: Option<LightAnimation>, title: &str
+) {
+    run_scene_with(objects, title, |state| {
+        if let Some(lights) = lights {
+            state.set_lights(lights);
+        }
+        if let Some(animation) = light_animation {
+            state.set_light_animation(animation);
+        }
+    });
+}
+
+/// Like [`run_scene`], calling `setup` on the state before the first frame.
+pub fn run_scene_with(
+    objects: &[SceneObject], title: &str, setup: impl FnOnce(&mut State)
+) {
+    run_graph_with(SceneGraph::from_objects(objects), title, setup);
+}
+
+/// Like [`run_scene_with`], drawing the nodes of `graph`.
+pub fn run_graph_with(graph: SceneGraph, title: &str, setup: impl FnOnce(&mut State)) {
+    // exit on a mistyped flag before a window opens
+    let screenshot_args = config::or_exit(screenshot::ScreenshotArgs::from_env());
+
+    let (event_loop, window) = app::create_window(title);
+    let mut state = config::or_exit(pollster::block_on(
+        State::new_graph(window, graph)
+    ));
+
+    setup(&mut state);
+
+    if shader::hot_reload_enabled() {
+        state.watch_shader(SHADER_PATH);
+    }
+
+    app::run_loop(event_loop, state, screenshot_args);
+}
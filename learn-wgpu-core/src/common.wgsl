@@ -0,0 +1,153 @@
+// vertex stage, lights and shadows of the lit shaders; shader.wgsl and
+// pbr.wgsl are appended to this file before they are compiled
+
+// vertex shader
+
+struct Uniforms {
+    model_mat : mat4x4<f32>,
+    view_project_mat : mat4x4<f32>,
+    normal_mat : mat4x4<f32>,
+};
+
+@binding(0) @group(0) var<uniform> uniforms : Uniforms;
+
+// placement of one copy of the object, relative to its model matrix
+struct Instance {
+    @location(3) model_mat_0 : vec4<f32>,
+    @location(4) model_mat_1 : vec4<f32>,
+    @location(5) model_mat_2 : vec4<f32>,
+    @location(6) model_mat_3 : vec4<f32>,
+    @location(7) normal_mat_0 : vec4<f32>,
+    @location(8) normal_mat_1 : vec4<f32>,
+    @location(9) normal_mat_2 : vec4<f32>,
+    @location(10) normal_mat_3 : vec4<f32>,
+    @location(11) color : vec4<f32>,
+};
+
+struct Output {
+    @builtin(position) position : vec4<f32>,
+    @location(0) v_position : vec4<f32>,
+    @location(1) v_normal : vec4<f32>,
+    @location(2) v_uv : vec2<f32>,
+    @location(3) v_color : vec4<f32>,
+};
+
+@vertex
+fn vs_main(
+    @location(0) pos : vec4<f32>,
+    @location(1) normal : vec4<f32>,
+    @location(2) uv : vec2<f32>,
+    instance : Instance
+) -> Output {
+    let model_mat = mat4x4<f32>(
+        instance.model_mat_0, instance.model_mat_1, instance.model_mat_2, instance.model_mat_3
+    );
+    let normal_mat = mat4x4<f32>(
+        instance.normal_mat_0, instance.normal_mat_1, instance.normal_mat_2, instance.normal_mat_3
+    );
+    var output: Output;
+    let m_position : vec4<f32> = uniforms.model_mat * model_mat * pos;
+    output.v_position = m_position;
+    output.v_normal = uniforms.normal_mat * normal_mat * normal;
+    output.v_uv = uv;
+    output.v_color = instance.color;
+    output.position = uniforms.view_project_mat * m_position;
+    return output;
+}
+
+
+// fragment shader
+
+const MAX_LIGHTS : u32 = 8u;
+const POINT_LIGHT : u32 = 0u;
+const DIRECTIONAL_LIGHT : u32 = 1u;
+const SPOT_LIGHT : u32 = 2u;
+
+struct LightSource {
+    position : vec4<f32>,
+    direction : vec4<f32>,
+    color : vec4<f32>,
+    // constant, linear, quadratic
+    attenuation : vec4<f32>,
+    kind : u32,
+    cos_inner : f32,
+    cos_outer : f32,
+    intensity : f32,
+    // layer of the shadow map, or -1
+    shadow_layer : i32,
+};
+
+struct FragUniforms {
+    eye_position : vec4<f32>,
+    light_count : u32,
+    lights : array<LightSource, MAX_LIGHTS>,
+};
+
+@binding(1) @group(0) var<uniform> frag_uniforms : FragUniforms;
+
+const MAX_SHADOWS : u32 = 4u;
+
+struct ShadowUniforms {
+    view_project : array<mat4x4<f32>, MAX_SHADOWS>,
+    pcf_radius : u32,
+};
+
+@binding(0) @group(2) var<uniform> shadow_uniforms : ShadowUniforms;
+@binding(1) @group(2) var shadow_map : texture_depth_2d_array;
+@binding(2) @group(2) var shadow_sampler : sampler_comparison;
+
+// share of the light reaching the position, averaged over a square of
+// shadow map texels
+fn shadow(layer : i32, position : vec3<f32>) -> f32 {
+    if (layer < 0) {
+        return 1.0;
+    }
+    let clip : vec4<f32> = shadow_uniforms.view_project[layer] * vec4<f32>(position, 1.0);
+    let ndc : vec3<f32> = clip.xyz / clip.w;
+    // outside the light's frustum nothing is in the way
+    if (clip.w <= 0.0 || ndc.z > 1.0 || any(abs(ndc.xy) > vec2<f32>(1.0))) {
+        return 1.0;
+    }
+    let uv : vec2<f32> = ndc.xy * vec2<f32>(0.5, -0.5) + 0.5;
+    let texel : vec2<f32> = 1.0 / vec2<f32>(textureDimensions(shadow_map));
+    let r : i32 = i32(shadow_uniforms.pcf_radius);
+    var lit : f32 = 0.0;
+    for (var y : i32 = -r; y <= r; y++) {
+        for (var x : i32 = -r; x <= r; x++) {
+            lit += textureSampleCompareLevel(
+                shadow_map, shadow_sampler, uv + vec2<f32>(f32(x), f32(y)) * texel,
+                layer, ndc.z
+            );
+        }
+    }
+    let n : f32 = f32(2 * r + 1);
+    return lit / (n * n);
+}
+
+// direction towards the light and its color at the given position
+struct Incident {
+    L : vec3<f32>,
+    radiance : vec3<f32>,
+};
+
+fn incident(light : LightSource, position : vec3<f32>) -> Incident {
+    var result : Incident;
+    let radiance : vec3<f32> = light.color.rgb * light.intensity;
+    if (light.kind == DIRECTIONAL_LIGHT) {
+        result.L = -light.direction.xyz;
+        result.radiance = radiance * shadow(light.shadow_layer, position);
+        return result;
+    }
+    let to_light : vec3<f32> = light.position.xyz - position;
+    let d : f32 = length(to_light);
+    result.L = to_light / d;
+    let a : vec4<f32> = light.attenuation;
+    var falloff : f32 = 1.0 / (a.x + a.y * d + a.z * d * d);
+    if (light.kind == SPOT_LIGHT) {
+        falloff = falloff * smoothstep(
+            light.cos_outer, light.cos_inner, dot(-result.L, light.direction.xyz)
+        );
+    }
+    result.radiance = radiance * falloff * shadow(light.shadow_layer, position);
+    return result;
+}
@@ -0,0 +1,403 @@
+//! Adapter, device and surface selection.
+//!
+//! [`WgpuConfig::from_env`] starts from the defaults and applies these
+//! overrides, command line flags taking precedence over the environment:
+//!
+//! | flag                            | variable                 | values                                   |
+//! |---------------------------------|--------------------------|------------------------------------------|
+//! | `--backend <list>`              | `WGPU_BACKEND`           | comma list of `vulkan`, `dx12`, `metal`, `gl` |
+//! | `--power <pref>`                | `WGPU_POWER_PREF`        | `low`, `high`, `none`                    |
+//! | `--adapter <name>`              | `WGPU_ADAPTER_NAME`      | case-insensitive part of the adapter name |
+//! | `--fallback-adapter`            | `WGPU_FALLBACK_ADAPTER`  | `1` to force a software adapter          |
+//! | `--features <list>`             | `WGPU_FEATURES`          | comma list of `wgpu::Features` names     |
+//! | `--limits <preset>`             | `WGPU_LIMITS`            | `default`, `downlevel`, `webgl2`         |
+//! | `--surface-format <kind>`       | `WGPU_SURFACE_FORMAT`    | `srgb`, `linear`                         |
+//! | `--present-mode <mode>`         | `WGPU_PRESENT_MODE`      | `fifo`, `fifo-relaxed`, `mailbox`, `immediate`, `auto-vsync`, `auto-no-vsync` |
+
+use std::fmt;
+
+/// Flags read by [`WgpuConfig::from_env`] that take a value, so programs
+/// with their own arguments can skip them.
+pub const VALUE_FLAGS: &[&str] = &[
+    "--backend", "--power", "--adapter", "--features", "--limits",
+    "--surface-format", "--present-mode",
+];
+
+/// Flags read by [`WgpuConfig::from_env`] that take no value.
+pub const SWITCH_FLAGS: &[&str] = &["--fallback-adapter"];
+
+/// Features enabled whenever the adapter has them, on top of
+/// [`WgpuConfig::required_features`]. The adapter-specific format features
+/// allow sample counts other than 1 and 4.
+pub const OPTIONAL_FEATURES: wgpu::Features =
+    wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+
+#[derive(Debug)]
+pub enum InitError {
+    /// An override could not be parsed.
+    Config(String),
+    Surface(wgpu::CreateSurfaceError),
+    NoAdapter {
+        config: Box<WgpuConfig>,
+        available: Vec<wgpu::AdapterInfo>,
+    },
+    /// `adapter` describes the adapter as "name (backend, device type)".
+    MissingFeatures {
+        adapter: String,
+        missing: wgpu::Features,
+    },
+    Device {
+        adapter: String,
+        error: wgpu::RequestDeviceError,
+    },
+}
+
+impl fmt::Display for InitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InitError::Config(message) => write!(f, "invalid wgpu configuration: {}", message),
+            InitError::Surface(e) => write!(f, "failed to create surface: {}", e),
+            InitError::NoAdapter { config, available } => {
+                write!(
+                    f,
+                    "no adapter matches backends {:?}, power preference {:?}",
+                    config.backends, config.power_preference
+                )?;
+                if let Some(name) = &config.adapter_name {
+                    write!(f, ", name \"{}\"", name)?;
+                }
+                if config.force_fallback_adapter {
+                    write!(f, ", fallback adapter only")?;
+                }
+                writeln!(f)?;
+                if available.is_empty() {
+                    write!(f, "no adapters are available on this system")
+                } else {
+                    write!(f, "available adapters:")?;
+                    for info in available {
+                        write!(f, "\n  {}", describe(info))?;
+                    }
+                    Ok(())
+                }
+            },
+            InitError::MissingFeatures { adapter, missing } => write!(
+                f, "adapter {} does not support the required features {:?}",
+                adapter, missing
+            ),
+            InitError::Device { adapter, error } => write!(
+                f, "failed to create a device on {}: {}", adapter, error
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InitError {}
+
+fn describe(info: &wgpu::AdapterInfo) -> String {
+    format!("{} ({:?}, {:?})", info.name, info.backend, info.device_type)
+}
+
+/// Prints the error and exits, for programs that cannot run without a GPU
+/// or with invalid command line arguments.
+pub fn or_exit<T>(result: Result<T, impl fmt::Display>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+#[derive(Clone, Debug)]
+pub struct WgpuConfig {
+    pub backends: wgpu::Backends,
+    pub power_preference: wgpu::PowerPreference,
+    /// Only adapters whose name contains this (case-insensitive) are used.
+    pub adapter_name: Option<String>,
+    pub force_fallback_adapter: bool,
+    pub required_features: wgpu::Features,
+    /// Texture size limits are raised to what the adapter supports.
+    pub required_limits: wgpu::Limits,
+    /// Prefer an sRGB surface format; otherwise a linear one.
+    pub srgb: bool,
+    /// Falls back to `Fifo`, which every surface supports, if unavailable.
+    pub present_mode: wgpu::PresentMode,
+}
+
+impl Default for WgpuConfig {
+    fn default() -> Self {
+        Self {
+            backends: wgpu::Backends::all(),
+            power_preference: wgpu::PowerPreference::default(),
+            adapter_name: None,
+            force_fallback_adapter: false,
+            required_features: wgpu::Features::empty(),
+            required_limits: wgpu::Limits::default(),
+            srgb: true,
+            present_mode: wgpu::PresentMode::Fifo,
+        }
+    }
+}
+
+impl WgpuConfig {
+    /// Defaults with the environment and command line overrides applied.
+    pub fn from_env() -> Result<Self, InitError> {
+        let mut config = Self::default();
+        let vars = [
+            ("WGPU_BACKEND", "--backend"),
+            ("WGPU_POWER_PREF", "--power"),
+            ("WGPU_ADAPTER_NAME", "--adapter"),
+            ("WGPU_FALLBACK_ADAPTER", "--fallback-adapter"),
+            ("WGPU_FEATURES", "--features"),
+            ("WGPU_LIMITS", "--limits"),
+            ("WGPU_SURFACE_FORMAT", "--surface-format"),
+            ("WGPU_PRESENT_MODE", "--present-mode"),
+        ];
+        for (var, flag) in vars {
+            if let Ok(value) = std::env::var(var) {
+                config.apply(flag, &value)?;
+            }
+        }
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if SWITCH_FLAGS.contains(&arg.as_str()) {
+                config.apply(&arg, "1")?;
+            } else if VALUE_FLAGS.contains(&arg.as_str()) {
+                let value = args.next().ok_or_else(|| {
+                    InitError::Config(format!("{} expects a value", arg))
+                })?;
+                config.apply(&arg, &value)?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// Applies one override, given by its command line flag.
+    pub fn apply(&mut self, flag: &str, value: &str) -> Result<(), InitError> {
+        let invalid = || InitError::Config(format!("unknown value \"{}\" for {}", value, flag));
+        let value_lower = value.to_lowercase();
+        match flag {
+            "--backend" => {
+                self.backends = wgpu::Backends::from_comma_list(value);
+                if self.backends.is_empty() {
+                    return Err(invalid());
+                }
+            },
+            "--power" => {
+                self.power_preference = match value_lower.as_str() {
+                    "low" => wgpu::PowerPreference::LowPower,
+                    "high" => wgpu::PowerPreference::HighPerformance,
+                    "none" => wgpu::PowerPreference::None,
+                    _ => return Err(invalid()),
+                };
+            },
+            "--adapter" => self.adapter_name = Some(value.to_string()),
+            "--fallback-adapter" => {
+                self.force_fallback_adapter = matches!(value_lower.as_str(), "1" | "true" | "yes");
+            },
+            "--features" => {
+                for name in value.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+                    let feature = wgpu::Features::from_name(&name.to_uppercase())
+                        .ok_or_else(|| InitError::Config(format!("unknown feature \"{}\"", name)))?;
+                    self.required_features |= feature;
+                }
+            },
+            "--limits" => {
+                self.required_limits = match value_lower.as_str() {
+                    "default" => wgpu::Limits::default(),
+                    "downlevel" => wgpu::Limits::downlevel_defaults(),
+                    "webgl2" => wgpu::Limits::downlevel_webgl2_defaults(),
+                    _ => return Err(invalid()),
+                };
+            },
+            "--surface-format" => {
+                self.srgb = match value_lower.as_str() {
+                    "srgb" => true,
+                    "linear" => false,
+                    _ => return Err(invalid()),
+                };
+            },
+            "--present-mode" => {
+                self.present_mode = match value_lower.as_str() {
+                    "fifo" => wgpu::PresentMode::Fifo,
+                    "fifo-relaxed" => wgpu::PresentMode::FifoRelaxed,
+                    "mailbox" => wgpu::PresentMode::Mailbox,
+                    "immediate" => wgpu::PresentMode::Immediate,
+                    "auto-vsync" => wgpu::PresentMode::AutoVsync,
+                    "auto-no-vsync" => wgpu::PresentMode::AutoNoVsync,
+                    _ => return Err(invalid()),
+                };
+            },
+            _ => return Err(InitError::Config(format!("unknown flag {}", flag))),
+        }
+        Ok(())
+    }
+
+    pub fn create_instance(&self) -> wgpu::Instance {
+        wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: self.backends,
+            ..Default::default()
+        })
+    }
+
+    /// Picks an adapter that can present to `surface`, if given. On failure
+    /// the error lists every adapter on the system.
+    pub async fn request_adapter(
+        &self, instance: &wgpu::Instance, surface: Option<&wgpu::Surface<'_>>
+    ) -> Result<wgpu::Adapter, InitError> {
+        let adapter = match &self.adapter_name {
+            Some(name) => {
+                let name = name.to_lowercase();
+                instance
+                    .enumerate_adapters(self.backends)
+                    .await
+                    .into_iter()
+                    .filter(|a| a.get_info().name.to_lowercase().contains(&name))
+                    .filter(|a| {
+                        !self.force_fallback_adapter
+                            || a.get_info().device_type == wgpu::DeviceType::Cpu
+                    })
+                    .find(|a| surface.is_none_or(|s| a.is_surface_supported(s)))
+            },
+            None => instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: self.power_preference,
+                    compatible_surface: surface,
+                    force_fallback_adapter: self.force_fallback_adapter,
+                })
+                .await
+                .ok(),
+        };
+        match adapter {
+            Some(adapter) => Ok(adapter),
+            None => Err(InitError::NoAdapter {
+                config: Box::new(self.clone()),
+                available: available_adapters().await,
+            }),
+        }
+    }
+
+    pub async fn request_device(
+        &self, adapter: &wgpu::Adapter
+    ) -> Result<(wgpu::Device, wgpu::Queue), InitError> {
+        let missing = self.required_features - adapter.features();
+        if !missing.is_empty() {
+            return Err(InitError::MissingFeatures {
+                adapter: describe(&adapter.get_info()),
+                missing,
+            });
+        }
+        adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                label: None,
+                required_features: self.required_features
+                    | (OPTIONAL_FEATURES & adapter.features()),
+                experimental_features: wgpu::ExperimentalFeatures::disabled(),
+                required_limits: self.required_limits.clone()
+                    .using_resolution(adapter.limits()),
+                memory_hints: Default::default(),
+                trace: wgpu::Trace::Off,
+            })
+            .await
+            .map_err(|error| InitError::Device { adapter: describe(&adapter.get_info()), error })
+    }
+
+    /// First supported format with the preferred color space, or the
+    /// surface's preferred format if there is none.
+    pub fn surface_format(&self, caps: &wgpu::SurfaceCapabilities) -> wgpu::TextureFormat {
+        caps.formats
+            .iter()
+            .copied()
+            .find(|format| format.is_srgb() == self.srgb)
+            .unwrap_or(caps.formats[0])
+    }
+
+    pub fn present_mode(&self, caps: &wgpu::SurfaceCapabilities) -> wgpu::PresentMode {
+        let automatic = matches!(
+            self.present_mode,
+            wgpu::PresentMode::AutoVsync | wgpu::PresentMode::AutoNoVsync
+        );
+        if automatic || caps.present_modes.contains(&self.present_mode) {
+            self.present_mode
+        } else {
+            eprintln!(
+                "Present mode {:?} is not supported, using Fifo", self.present_mode
+            );
+            wgpu::PresentMode::Fifo
+        }
+    }
+}
+
+/// Every adapter on every backend, for error messages.
+pub async fn available_adapters() -> Vec<wgpu::AdapterInfo> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        ..Default::default()
+    });
+    instance
+        .enumerate_adapters(wgpu::Backends::all())
+        .await
+        .iter()
+        .map(|adapter| adapter.get_info())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_overrides() {
+        let mut config = WgpuConfig::default();
+        config.apply("--backend", "vk, gl").unwrap();
+        config.apply("--power", "HIGH").unwrap();
+        config.apply("--features", "depth_clip_control").unwrap();
+        config.apply("--surface-format", "linear").unwrap();
+        config.apply("--present-mode", "mailbox").unwrap();
+        assert_eq!(config.backends, wgpu::Backends::VULKAN | wgpu::Backends::GL);
+        assert_eq!(config.power_preference, wgpu::PowerPreference::HighPerformance);
+        assert_eq!(config.required_features, wgpu::Features::DEPTH_CLIP_CONTROL);
+        assert!(!config.srgb);
+        assert_eq!(config.present_mode, wgpu::PresentMode::Mailbox);
+    }
+
+    #[test]
+    fn rejects_unknown_values() {
+        let mut config = WgpuConfig::default();
+        for (flag, value) in [
+            ("--backend", "glide"),
+            ("--power", "medium"),
+            ("--features", "warp_drive"),
+            ("--present-mode", "sometimes"),
+        ] {
+            let e = config.apply(flag, value).unwrap_err();
+            assert!(matches!(e, InitError::Config(_)), "{}", e);
+        }
+    }
+
+    #[test]
+    fn no_adapter_error_lists_adapters() {
+        let info = wgpu::AdapterInfo {
+            name: "llvmpipe".to_string(),
+            vendor: 0,
+            device: 0,
+            device_type: wgpu::DeviceType::Cpu,
+            device_pci_bus_id: String::new(),
+            driver: String::new(),
+            driver_info: String::new(),
+            backend: wgpu::Backend::Gl,
+            subgroup_min_size: 0,
+            subgroup_max_size: 0,
+            transient_saves_memory: false,
+        };
+        let e = InitError::NoAdapter {
+            config: Box::new(WgpuConfig {
+                backends: wgpu::Backends::METAL,
+                ..Default::default()
+            }),
+            available: vec![info],
+        };
+        let message = e.to_string();
+        assert!(message.contains("METAL"), "{}", message);
+        assert!(message.contains("llvmpipe (Gl, Cpu)"), "{}", message);
+    }
+}
@@ -0,0 +1,118 @@
+//! The depth buffer of the main render pass, owned by the renderer and
+//! recreated only when the window is resized.
+//!
+//! With reversed-Z the projection maps the near plane to depth 1 and the
+//! far plane to 0 (see [`crate::transforms::reverse_z`]). Paired with
+//! `Depth32Float` this spreads the float precision evenly over the view
+//! distance instead of spending most of it right in front of the camera.
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DepthConfig {
+    /// One of the depth formats, e.g. `Depth24Plus` (the default),
+    /// `Depth32Float` or `Depth24PlusStencil8`.
+    pub format: wgpu::TextureFormat,
+    pub reversed_z: bool,
+}
+
+impl Default for DepthConfig {
+    fn default() -> Self {
+        Self { format: wgpu::TextureFormat::Depth24Plus, reversed_z: false }
+    }
+}
+
+impl DepthConfig {
+    /// Panics unless `format` has a depth aspect.
+    pub fn new(format: wgpu::TextureFormat, reversed_z: bool) -> Self {
+        assert!(format.has_depth_aspect(), "{:?} is not a depth format", format);
+        Self { format, reversed_z }
+    }
+
+    /// Nearer fragments pass; with reversed-Z they have greater depth.
+    pub fn compare(&self) -> wgpu::CompareFunction {
+        if self.reversed_z {
+            wgpu::CompareFunction::GreaterEqual
+        } else {
+            wgpu::CompareFunction::LessEqual
+        }
+    }
+
+    /// Depth of the far plane, which the buffer is cleared to.
+    pub fn clear_value(&self) -> f32 {
+        if self.reversed_z { 0.0 } else { 1.0 }
+    }
+
+    /// Depth state for pipelines drawing into a [`DepthBuffer`] with this
+    /// config.
+    pub fn depth_stencil_state(&self) -> wgpu::DepthStencilState {
+        wgpu::DepthStencilState {
+            format: self.format,
+            depth_write_enabled: true,
+            depth_compare: self.compare(),
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }
+    }
+}
+
+pub struct DepthBuffer {
+    config: DepthConfig,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+impl DepthBuffer {
+    pub fn new(device: &wgpu::Device, width: u32, height: u32, config: DepthConfig) -> Self {
+        Self::multisampled(device, width, height, config, 1)
+    }
+
+    /// A depth buffer for a pass drawing into a multisampled color target.
+    pub fn multisampled(
+        device: &wgpu::Device, width: u32, height: u32, config: DepthConfig, sample_count: u32
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self { config, texture, view }
+    }
+
+    /// Recreates the texture if the size changed.
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        if (self.texture.width(), self.texture.height()) != (width, height) {
+            *self = Self::multisampled(
+                device, width, height, self.config, self.texture.sample_count()
+            );
+        }
+    }
+
+    pub fn config(&self) -> &DepthConfig {
+        &self.config
+    }
+
+    /// Clears depth, and stencil if the format has it, at the start of a
+    /// pass; neither is kept afterwards.
+    pub fn attachment(&self) -> wgpu::RenderPassDepthStencilAttachment<'_> {
+        wgpu::RenderPassDepthStencilAttachment {
+            view: &self.view,
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Clear(self.config.clear_value()),
+                store: wgpu::StoreOp::Discard,
+            }),
+            stencil_ops: self.config.format.has_stencil_aspect().then_some(wgpu::Operations {
+                load: wgpu::LoadOp::Clear(0),
+                store: wgpu::StoreOp::Discard,
+            }),
+        }
+    }
+}
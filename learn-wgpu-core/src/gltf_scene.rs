@@ -0,0 +1,286 @@
+//! glTF 2.0 import (`.gltf` with external or embedded buffers and `.glb`)
+//! into [`common::SceneObject`]s.
+//!
+//! Every triangle primitive reachable from the default scene becomes one
+//! object. Node transforms are composed down the hierarchy into the
+//! object's model matrix, and the PBR metallic-roughness factors of the
+//! primitive's material are approximated by the Blinn-Phong [`Material`]
+//! terms used by the lit examples.
+
+use std::fmt;
+use std::path::Path;
+
+use cgmath::*;
+
+use crate::{common, mesh, transforms};
+use crate::material::Material;
+
+#[derive(Debug)]
+pub enum GltfError {
+    Import(gltf::Error),
+    NoScene,
+    MissingPositions { mesh: usize },
+    UnsupportedMode { mesh: usize, mode: gltf::mesh::Mode },
+}
+
+impl fmt::Display for GltfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GltfError::Import(e) => write!(f, "{}", e),
+            GltfError::NoScene => write!(f, "the file contains no scene"),
+            GltfError::MissingPositions { mesh } =>
+                write!(f, "mesh {}: primitive has no POSITION attribute", mesh),
+            GltfError::UnsupportedMode { mesh, mode } =>
+                write!(f, "mesh {}: unsupported primitive mode {:?}", mesh, mode),
+        }
+    }
+}
+
+impl std::error::Error for GltfError {}
+
+impl From<gltf::Error> for GltfError {
+    fn from(e: gltf::Error) -> Self {
+        GltfError::Import(e)
+    }
+}
+
+/// Loads a `.gltf` or `.glb` file; external buffers are resolved relative
+/// to it.
+pub fn load_gltf(path: impl AsRef<Path>) -> Result<Vec<common::SceneObject>, GltfError> {
+    let (document, buffers, _) = gltf::import(path)?;
+    scene_objects(&document, &buffers)
+}
+
+/// Same as [`load_gltf`] for a file already in memory. Only embedded
+/// (`data:` URI or GLB) buffers are supported.
+pub fn parse_gltf(bytes: &[u8]) -> Result<Vec<common::SceneObject>, GltfError> {
+    let (document, buffers, _) = gltf::import_slice(bytes)?;
+    scene_objects(&document, &buffers)
+}
+
+fn scene_objects(
+    document: &gltf::Document, buffers: &[gltf::buffer::Data]
+) -> Result<Vec<common::SceneObject>, GltfError> {
+    let scene = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .ok_or(GltfError::NoScene)?;
+
+    let mut objects = Vec::new();
+    for node in scene.nodes() {
+        add_node(&node, Matrix4::identity(), buffers, &mut objects)?;
+    }
+    Ok(objects)
+}
+
+fn add_node(
+    node: &gltf::Node, parent_mat: Matrix4<f32>, buffers: &[gltf::buffer::Data],
+    objects: &mut Vec<common::SceneObject>
+) -> Result<(), GltfError> {
+    // glTF stores the rotation as [x, y, z, w]
+    let (translation, [x, y, z, w], scaling) = node.transform().decomposed();
+    let transform = transforms::Transform::new(translation, Quaternion::new(w, x, y, z), scaling);
+    let model_mat = parent_mat * transform.to_matrix();
+
+    if let Some(mesh) = node.mesh() {
+        for primitive in mesh.primitives() {
+            let (vertex_data, indices) = primitive_vertices(&mesh, &primitive, buffers)?;
+            objects.push(common::SceneObject {
+                vertex_data: vertex_data.into_iter().map(Into::into).collect(),
+                indices,
+                model_mat,
+                material: convert_material(&primitive.material()).into(),
+            });
+        }
+    }
+    for child in node.children() {
+        add_node(&child, model_mat, buffers, objects)?;
+    }
+    Ok(())
+}
+
+// keeps the primitive's indices when it has normals; otherwise de-indexes it
+// so every triangle can get its own flat normal
+fn primitive_vertices(
+    mesh: &gltf::Mesh, primitive: &gltf::Primitive, buffers: &[gltf::buffer::Data]
+) -> Result<(Vec<common::Vertex>, Option<mesh::Indices>), GltfError> {
+    if primitive.mode() != gltf::mesh::Mode::Triangles {
+        return Err(GltfError::UnsupportedMode {
+            mesh: mesh.index(), mode: primitive.mode()
+        });
+    }
+    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+    let positions: Vec<[f32; 3]> = reader
+        .read_positions()
+        .ok_or(GltfError::MissingPositions { mesh: mesh.index() })?
+        .collect();
+    let normals: Option<Vec<[f32; 3]>> = reader.read_normals().map(|n| n.collect());
+    let indices: Vec<u32> = match reader.read_indices() {
+        Some(indices) => indices.into_u32().collect(),
+        None => (0..positions.len() as u32).collect(),
+    };
+
+    if let Some(normals) = normals {
+        let mesh = mesh::IndexedMesh::new(&positions, &normals, indices);
+        return Ok((mesh.vertices, Some(mesh.indices)));
+    }
+
+    // flat normals, as the spec asks for when NORMAL is missing
+    let mut vertices = Vec::with_capacity(indices.len());
+    for triangle in indices.chunks_exact(3) {
+        let p = [0, 1, 2].map(|k| positions[triangle[k] as usize]);
+        let n = triangle_normal(p[0], p[1], p[2]);
+        for p in p {
+            vertices.push(common::Vertex {
+                position: [p[0], p[1], p[2], 1.0],
+                normal: [n[0], n[1], n[2], 1.0],
+            });
+        }
+    }
+    Ok((vertices, None))
+}
+
+fn triangle_normal(p0: [f32; 3], p1: [f32; 3], p2: [f32; 3]) -> [f32; 3] {
+    let (p0, p1, p2) = (Vector3::from(p0), Vector3::from(p1), Vector3::from(p2));
+    let n = (p1 - p0).cross(p2 - p0);
+    if n.magnitude2() > 0.0 { n.normalize().into() } else { [0.0, 0.0, 0.0] }
+}
+
+/// Maps a PBR metallic-roughness material onto a Blinn-Phong one: the base
+/// color becomes the diffuse color, metals tint their highlights with it,
+/// and rougher surfaces get broader highlights.
+pub fn convert_material(material: &gltf::Material) -> Material {
+    let pbr = material.pbr_metallic_roughness();
+    let base = pbr.base_color_factor();
+    let base = [base[0], base[1], base[2]];
+    let metallic = pbr.metallic_factor();
+    let specular = base.map(|c| 1.0 + (c - 1.0) * metallic);
+    common::material(base, specular, 0.1, 0.6, 0.7, roughness_to_shininess(pbr.roughness_factor()))
+}
+
+// Blinn-Phong exponent whose lobe roughly matches a GGX lobe of the given
+// perceptual roughness
+fn roughness_to_shininess(roughness: f32) -> f32 {
+    let alpha2 = roughness.powi(4).max(1e-4);
+    (2.0 / alpha2 - 2.0).clamp(1.0, 512.0)
+}
+
+/// Prepends a transform to every model matrix so the whole scene is centered
+/// on the origin and fits in a sphere of the given radius.
+pub fn fit_to_view(objects: &mut [common::SceneObject], radius: f32) {
+    let points: Vec<Point3<f32>> = objects
+        .iter()
+        .flat_map(|object| object.vertex_data.iter().map(|v| {
+            object.model_mat.transform_point(Point3::new(
+                v.position[0], v.position[1], v.position[2]
+            ))
+        }))
+        .collect();
+    if points.is_empty() {
+        return;
+    }
+
+    let mut min = points[0];
+    let mut max = points[0];
+    for p in &points {
+        min = Point3::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z));
+        max = Point3::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z));
+    }
+    let center = min.midpoint(max);
+    let extent = points
+        .iter()
+        .map(|p| p.distance(center))
+        .fold(0.0, f32::max);
+    let scale = if extent > 0.0 { radius / extent } else { 1.0 };
+
+    let fit_mat = Matrix4::from_scale(scale)
+        * Matrix4::from_translation(Point3::origin() - center);
+    for object in objects.iter_mut() {
+        object.model_mat = fit_mat * object.model_mat;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // one triangle in the xy plane without normals, referenced by a child
+    // node below a translated and scaled parent
+    const TRIANGLE: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [
+            { "translation": [1, 2, 3], "scale": [2, 2, 2], "children": [1] },
+            { "mesh": 0, "rotation": [0, 0.7071068, 0, 0.7071068] }
+        ],
+        "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 }, "material": 0 }] }],
+        "materials": [{
+            "pbrMetallicRoughness": {
+                "baseColorFactor": [1, 0.5, 0, 1],
+                "metallicFactor": 1,
+                "roughnessFactor": 1
+            }
+        }],
+        "accessors": [{
+            "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+            "min": [0, 0, 0], "max": [1, 1, 0]
+        }],
+        "bufferViews": [{ "buffer": 0, "byteLength": 36 }],
+        "buffers": [{
+            "byteLength": 36,
+            "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA"
+        }]
+    }"#;
+
+    fn assert_near(a: [f32; 3], b: [f32; 3]) {
+        for (a, b) in a.iter().zip(b) {
+            assert!((a - b).abs() < 1e-5, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn composes_node_transforms() {
+        let objects = parse_gltf(TRIANGLE.as_bytes()).unwrap();
+        assert_eq!(objects.len(), 1);
+        let object = &objects[0];
+        assert_eq!(object.vertex_data.len(), 3);
+
+        // the child rotates +x onto -z, the parent scales by 2 and translates
+        let p = object.model_mat.transform_point(Point3::new(1.0, 0.0, 0.0));
+        assert_near(p.into(), [1.0, 2.0, 1.0]);
+        let p = object.model_mat.transform_point(Point3::new(0.0, 1.0, 0.0));
+        assert_near(p.into(), [1.0, 4.0, 3.0]);
+    }
+
+    #[test]
+    fn generates_flat_normals() {
+        let objects = parse_gltf(TRIANGLE.as_bytes()).unwrap();
+        for v in &objects[0].vertex_data {
+            assert_near([v.normal[0], v.normal[1], v.normal[2]], [0.0, 0.0, 1.0]);
+        }
+    }
+
+    #[test]
+    fn maps_pbr_material_to_blinn_phong() {
+        let objects = parse_gltf(TRIANGLE.as_bytes()).unwrap();
+        // a rough metal: highlights tinted by the base color and very broad
+        let expected = common::material([1.0, 0.5, 0.0], [1.0, 0.5, 0.0], 0.1, 0.6, 0.7, 1.0);
+        assert_eq!(objects[0].material, expected.into());
+    }
+
+    #[test]
+    fn fits_scene_into_radius() {
+        let mut objects = parse_gltf(TRIANGLE.as_bytes()).unwrap();
+        fit_to_view(&mut objects, 2.0);
+        let object = &objects[0];
+        let max = object.vertex_data
+            .iter()
+            .map(|v| object.model_mat.transform_point(Point3::new(
+                v.position[0], v.position[1], v.position[2]
+            )))
+            .map(|p| p.to_vec().magnitude())
+            .fold(0.0, f32::max);
+        assert!((max - 2.0).abs() < 1e-4, "{}", max);
+    }
+}
@@ -0,0 +1,87 @@
+//! Golden-image comparisons for the offscreen-rendered example scenes.
+//!
+//! References are PNG files checked in next to each example. Setting the
+//! `UPDATE_GOLDEN` environment variable rewrites them from the current
+//! output instead of comparing.
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use image::RgbaImage;
+
+/// Size every golden scene is rendered at.
+pub const WIDTH: u32 = 160;
+pub const HEIGHT: u32 = 120;
+
+/// Largest per-channel difference for a pixel to still count as matching.
+pub const DEFAULT_TOLERANCE: u8 = 2;
+
+/// Compares tightly packed RGBA8 pixels against the PNG at `reference`.
+///
+/// Panics if any pixel differs by more than `tolerance` in any channel.
+/// On failure the rendered image and a diff image (mismatches in red over a
+/// dimmed reference) are written to a temporary directory whose path is
+/// included in the panic message.
+pub fn assert_matches(
+    reference: impl AsRef<Path>, width: u32, height: u32, pixels: &[u8],
+    tolerance: u8
+) {
+    let reference = reference.as_ref();
+    let actual = RgbaImage::from_raw(width, height, pixels.to_vec())
+        .expect("pixel buffer does not match the given size");
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        actual.save(reference).expect("Failed to write golden image");
+        return;
+    }
+
+    let expected = match image::open(reference) {
+        Ok(image) => image.to_rgba8(),
+        Err(e) => panic!(
+            "Failed to load golden image {}: {} \
+             (run with UPDATE_GOLDEN=1 to create it)",
+            reference.display(), e
+        ),
+    };
+    assert_eq!(
+        expected.dimensions(), actual.dimensions(),
+        "golden image {} has a different size", reference.display()
+    );
+
+    let mut diff = RgbaImage::new(width, height);
+    let mut mismatched = 0;
+    for (x, y, e) in expected.enumerate_pixels() {
+        let a = actual.get_pixel(x, y);
+        let matches = e.0.iter().zip(a.0.iter())
+            .all(|(e, a)| e.abs_diff(*a) <= tolerance);
+        let out = if matches {
+            let l = ((e[0] as u32 + e[1] as u32 + e[2] as u32) / 12) as u8;
+            [l, l, l, 255]
+        } else {
+            mismatched += 1;
+            [255, 0, 0, 255]
+        };
+        diff.put_pixel(x, y, image::Rgba(out));
+    }
+
+    if mismatched > 0 {
+        let (actual_path, diff_path) = failure_paths(reference);
+        actual.save(&actual_path).expect("Failed to write actual image");
+        diff.save(&diff_path).expect("Failed to write diff image");
+        panic!(
+            "{} of {} pixels differ from {} by more than {}\n  actual: {}\n  diff:   {}",
+            mismatched, width * height, reference.display(), tolerance,
+            actual_path.display(), diff_path.display()
+        );
+    }
+}
+
+fn failure_paths(reference: &Path) -> (PathBuf, PathBuf) {
+    let dir = env::temp_dir().join("learn-wgpu-golden");
+    std::fs::create_dir_all(&dir).expect("Failed to create golden output directory");
+    let stem = reference.file_stem().unwrap_or_default().to_string_lossy();
+    (
+        dir.join(format!("{}.actual.png", stem)),
+        dir.join(format!("{}.diff.png", stem)),
+    )
+}
@@ -0,0 +1,92 @@
+//! Per-instance data for drawing many transformed copies of a mesh in one
+//! draw call. Instances are read from a second vertex buffer stepped once
+//! per instance, at locations 3 to 11 of the lit shaders.
+
+use std::mem;
+use std::time::Duration;
+
+use bytemuck::{Pod, Zeroable};
+use cgmath::{Matrix, Matrix4, SquareMatrix};
+
+/// Moves the instances of one object on every
+/// [`crate::common::State::update`], given the time since the start.
+pub type InstanceAnimation = Box<dyn FnMut(&mut [Instance], Duration)>;
+
+/// One copy of an object, placed relative to the object's model matrix.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Instance {
+    pub model_mat: Matrix4<f32>,
+    /// Linear RGBA multiplying the material's diffuse or base color.
+    pub color: [f32; 4],
+}
+
+impl Default for Instance {
+    fn default() -> Self {
+        Self { model_mat: Matrix4::identity(), color: [1.0; 4] }
+    }
+}
+
+impl Instance {
+    pub fn new(model_mat: Matrix4<f32>, color: [f32; 3]) -> Self {
+        let [r, g, b] = color;
+        Self { model_mat, color: [r, g, b, 1.0] }
+    }
+
+    /// The normal matrix is the inverse transpose of `model_mat`, or the
+    /// identity if it is singular.
+    pub fn to_raw(&self) -> InstanceRaw {
+        let normal_mat = self.model_mat.invert().unwrap_or(Matrix4::identity()).transpose();
+        InstanceRaw {
+            model_mat: self.model_mat.into(),
+            normal_mat: normal_mat.into(),
+            color: self.color,
+        }
+    }
+}
+
+/// An [`Instance`] as laid out in the instance buffer.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct InstanceRaw {
+    pub model_mat: [[f32; 4]; 4],
+    pub normal_mat: [[f32; 4]; 4],
+    pub color: [f32; 4],
+}
+
+impl InstanceRaw {
+    // a matrix takes one location per column
+    const ATTRIBUTES: [wgpu::VertexAttribute; 9] = wgpu::vertex_attr_array![
+        3 => Float32x4, 4 => Float32x4, 5 => Float32x4, 6 => Float32x4,
+        7 => Float32x4, 8 => Float32x4, 9 => Float32x4, 10 => Float32x4,
+        11 => Float32x4
+    ];
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{InnerSpace, Vector4};
+
+    #[test]
+    fn normal_matrix_keeps_normals_perpendicular() {
+        let model_mat = Matrix4::from_translation([1.0, 2.0, 3.0].into())
+            * Matrix4::from_nonuniform_scale(4.0, 1.0, 0.5);
+        let raw = Instance::new(model_mat, [1.0, 0.5, 0.0]).to_raw();
+        assert_eq!(raw.color, [1.0, 0.5, 0.0, 1.0]);
+        let normal_mat = Matrix4::from(raw.normal_mat);
+        // a tangent and the normal of the plane x + y = 0
+        let tangent = model_mat * Vector4::new(1.0, -1.0, 0.0, 0.0);
+        let normal = normal_mat * Vector4::new(1.0, 1.0, 0.0, 0.0);
+        assert!(tangent.dot(normal).abs() < 1e-5);
+
+        let singular = Instance::new(Matrix4::from_scale(0.0), [1.0; 3]).to_raw();
+        assert_eq!(Matrix4::from(singular.normal_mat), Matrix4::identity());
+    }
+}
@@ -0,0 +1,31 @@
+//! Shared building blocks for the `wgpu07` and later examples: wgpu
+//! initialization (windowed or offscreen), view/projection/model transforms,
+//! vertex generators, the lit renderer with its event loop and golden-image
+//! comparisons for tests.
+
+pub mod animation;
+pub mod app;
+pub mod args;
+pub mod camera;
+pub mod common;
+pub mod config;
+pub mod depth;
+pub mod gltf_scene;
+pub mod golden;
+pub mod instance;
+pub mod lights;
+pub mod material;
+pub mod math_func;
+pub mod mesh;
+pub mod msaa;
+pub mod obj;
+pub mod parametric;
+pub mod readback;
+pub mod scene_graph;
+pub mod screenshot;
+pub mod shader;
+pub mod shadow;
+pub mod texture;
+pub mod transforms;
+pub mod uniforms;
+pub mod vertex_data;
@@ -0,0 +1,227 @@
+//! Light sources of the lit renderer. Up to [`MAX_LIGHTS`] are summed in
+//! the fragment shader; each object's [`crate::material::Material`] decides
+//! how strongly it reflects them.
+
+use std::time::Duration;
+
+use cgmath::{Angle, Deg, InnerSpace, Vector3};
+
+use crate::uniforms;
+
+/// Length of the light array in `shader.wgsl`.
+pub const MAX_LIGHTS: usize = 8;
+
+/// Called every frame with the lights and the time since start, so lights
+/// can move independently of the camera.
+pub type LightAnimation = Box<dyn FnMut(&mut [LightSource], Duration)>;
+
+/// Distance falloff `1 / (constant + linear * d + quadratic * d^2)`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Attenuation {
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+impl Attenuation {
+    /// No falloff with distance.
+    pub const NONE: Self = Self { constant: 1.0, linear: 0.0, quadratic: 0.0 };
+
+    /// Falloff that drops to about 1% of the intensity at `range`.
+    pub fn range(range: f32) -> Self {
+        Self { constant: 1.0, linear: 4.5 / range, quadratic: 75.0 / (range * range) }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LightSource {
+    /// Shines in all directions from `position`.
+    Point {
+        position: [f32; 3],
+        color: [f32; 3],
+        intensity: f32,
+        attenuation: Attenuation,
+    },
+    /// Parallel rays travelling along `direction`, like sunlight.
+    Directional {
+        direction: [f32; 3],
+        color: [f32; 3],
+        intensity: f32,
+    },
+    /// A cone along `direction`, at full intensity inside `inner_angle` and
+    /// fading out towards `outer_angle` (both measured from the axis).
+    Spot {
+        position: [f32; 3],
+        direction: [f32; 3],
+        color: [f32; 3],
+        intensity: f32,
+        attenuation: Attenuation,
+        inner_angle: Deg<f32>,
+        outer_angle: Deg<f32>,
+    },
+}
+
+impl LightSource {
+    pub fn point(position: [f32; 3], color: [f32; 3]) -> Self {
+        LightSource::Point { position, color, intensity: 1.0, attenuation: Attenuation::NONE }
+    }
+
+    pub fn directional(direction: [f32; 3], color: [f32; 3]) -> Self {
+        LightSource::Directional { direction, color, intensity: 1.0 }
+    }
+
+    pub fn spot(
+        position: [f32; 3], direction: [f32; 3], color: [f32; 3],
+        inner_angle: Deg<f32>, outer_angle: Deg<f32>
+    ) -> Self {
+        LightSource::Spot {
+            position, direction, color, intensity: 1.0, attenuation: Attenuation::NONE,
+            inner_angle, outer_angle,
+        }
+    }
+
+    pub fn with_intensity(mut self, value: f32) -> Self {
+        match &mut self {
+            LightSource::Point { intensity, .. }
+            | LightSource::Directional { intensity, .. }
+            | LightSource::Spot { intensity, .. } => *intensity = value,
+        }
+        self
+    }
+
+    /// Has no effect on directional lights.
+    pub fn with_attenuation(mut self, value: Attenuation) -> Self {
+        match &mut self {
+            LightSource::Point { attenuation, .. }
+            | LightSource::Spot { attenuation, .. } => *attenuation = value,
+            LightSource::Directional { .. } => {},
+        }
+        self
+    }
+
+    /// `None` for directional lights.
+    pub fn position(&self) -> Option<[f32; 3]> {
+        match self {
+            LightSource::Point { position, .. } | LightSource::Spot { position, .. } => Some(*position),
+            LightSource::Directional { .. } => None,
+        }
+    }
+
+    /// Moves point and spot lights; directional lights are unaffected.
+    pub fn set_position(&mut self, value: [f32; 3]) {
+        match self {
+            LightSource::Point { position, .. } | LightSource::Spot { position, .. } => *position = value,
+            LightSource::Directional { .. } => {},
+        }
+    }
+
+    /// Turns directional and spot lights; point lights are unaffected.
+    pub fn set_direction(&mut self, value: [f32; 3]) {
+        match self {
+            LightSource::Directional { direction, .. } | LightSource::Spot { direction, .. } => *direction = value,
+            LightSource::Point { .. } => {},
+        }
+    }
+
+    pub fn set_color(&mut self, value: [f32; 3]) {
+        match self {
+            LightSource::Point { color, .. }
+            | LightSource::Directional { color, .. }
+            | LightSource::Spot { color, .. } => *color = value,
+        }
+    }
+
+    pub fn set_intensity(&mut self, value: f32) {
+        *self = self.with_intensity(value);
+    }
+
+    pub fn to_uniforms(&self) -> uniforms::LightSourceUniforms {
+        let vec4 = |v: [f32; 3], w: f32| [v[0], v[1], v[2], w];
+        let unit = |v: [f32; 3]| -> [f32; 3] { Vector3::from(v).normalize().into() };
+        let attenuation = |a: &Attenuation| [a.constant, a.linear, a.quadratic, 0.0];
+        match self {
+            LightSource::Point { position, color, intensity, attenuation: a } =>
+                uniforms::LightSourceUniforms {
+                    position: vec4(*position, 1.0),
+                    direction: [0.0; 4],
+                    color: vec4(*color, 1.0),
+                    attenuation: attenuation(a),
+                    kind: uniforms::POINT_LIGHT,
+                    cos_inner: 0.0,
+                    cos_outer: 0.0,
+                    intensity: *intensity,
+                    shadow_layer: uniforms::NO_SHADOW,
+                    _padding: [0; 3],
+                },
+            LightSource::Directional { direction, color, intensity } =>
+                uniforms::LightSourceUniforms {
+                    position: [0.0; 4],
+                    direction: vec4(unit(*direction), 0.0),
+                    color: vec4(*color, 1.0),
+                    attenuation: attenuation(&Attenuation::NONE),
+                    kind: uniforms::DIRECTIONAL_LIGHT,
+                    cos_inner: 0.0,
+                    cos_outer: 0.0,
+                    intensity: *intensity,
+                    shadow_layer: uniforms::NO_SHADOW,
+                    _padding: [0; 3],
+                },
+            LightSource::Spot {
+                position, direction, color, intensity, attenuation: a, inner_angle, outer_angle
+            } => uniforms::LightSourceUniforms {
+                position: vec4(*position, 1.0),
+                direction: vec4(unit(*direction), 0.0),
+                color: vec4(*color, 1.0),
+                attenuation: attenuation(a),
+                kind: uniforms::SPOT_LIGHT,
+                cos_inner: inner_angle.cos(),
+                // keep the fade range non-empty for smoothstep
+                cos_outer: outer_angle.cos().min(inner_angle.cos() - 1e-4),
+                intensity: *intensity,
+                shadow_layer: uniforms::NO_SHADOW,
+                _padding: [0; 3],
+            },
+        }
+    }
+}
+
+/// The light the renderer starts with: white, at the initial camera
+/// position.
+pub fn default_lights(position: [f32; 3]) -> Vec<LightSource> {
+    vec![LightSource::point(position, [1.0, 1.0, 1.0])]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spot_cone_and_attenuation() {
+        let spot = LightSource::spot(
+            [0.0, 4.0, 0.0], [0.0, -2.0, 0.0], [1.0, 0.5, 0.0], Deg(20.0), Deg(30.0)
+        )
+            .with_intensity(2.0)
+            .with_attenuation(Attenuation::range(10.0));
+        let u = spot.to_uniforms();
+        assert_eq!(u.kind, uniforms::SPOT_LIGHT);
+        assert_eq!(u.direction, [0.0, -1.0, 0.0, 0.0]);
+        assert!(u.cos_outer < u.cos_inner);
+        assert_eq!(u.intensity, 2.0);
+
+        let falloff = |d: f32| 1.0 / (u.attenuation[0] + u.attenuation[1] * d + u.attenuation[2] * d * d);
+        assert_eq!(falloff(0.0), 1.0);
+        assert!((falloff(10.0) - 0.0125).abs() < 1e-3, "{}", falloff(10.0));
+    }
+
+    #[test]
+    fn directional_lights_ignore_position_changes() {
+        let mut sun = LightSource::directional([1.0, -1.0, 0.0], [1.0; 3]);
+        sun.set_position([1.0, 2.0, 3.0]);
+        assert_eq!(sun.position(), None);
+        assert_eq!(sun.with_attenuation(Attenuation::range(1.0)), sun);
+
+        let mut bulb = LightSource::point([0.0; 3], [1.0; 3]);
+        bulb.set_position([1.0, 2.0, 3.0]);
+        assert_eq!(bulb.position(), Some([1.0, 2.0, 3.0]));
+    }
+}
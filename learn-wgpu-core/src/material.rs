@@ -0,0 +1,244 @@
+//! Surface properties of the lit renderer, kept apart from the
+//! [`crate::lights`] that shine on them so objects with different materials
+//! can share one set of lights.
+//!
+//! Each object's material is bound per draw at group 1: the uniforms at
+//! binding 0, two textures at bindings 1 and 2 and their sampler at
+//! binding 3. Blinn-Phong [`Material`]s are drawn with `shader.wgsl` and
+//! metallic-roughness [`PbrMaterial`]s with `pbr.wgsl`.
+
+use crate::{texture, uniforms};
+
+/// Blinn-Phong reflectances, as in the `Ka`, `Kd`, `Ks` and `Ns` terms of
+/// an MTL file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+    /// Share of the ambient light reflected, per channel.
+    pub ambient: [f32; 3],
+    pub diffuse: [f32; 3],
+    pub specular: [f32; 3],
+    /// Higher values give smaller, sharper highlights.
+    pub shininess: f32,
+    /// Multiplies `ambient` and `diffuse`; its alpha is the output alpha.
+    pub diffuse_texture: Option<image::RgbaImage>,
+    /// Multiplies `specular`, e.g. to keep parts of a surface from shining.
+    pub specular_texture: Option<image::RgbaImage>,
+}
+
+impl Material {
+    /// A material with a tenth of the diffuse color as ambient color.
+    pub fn new(diffuse: [f32; 3], specular: [f32; 3], shininess: f32) -> Self {
+        Self {
+            ambient: diffuse.map(|c| 0.1 * c),
+            diffuse,
+            specular,
+            shininess,
+            diffuse_texture: None,
+            specular_texture: None,
+        }
+    }
+
+    pub fn with_diffuse_texture(mut self, image: image::RgbaImage) -> Self {
+        self.diffuse_texture = Some(image);
+        self
+    }
+
+    pub fn with_specular_texture(mut self, image: image::RgbaImage) -> Self {
+        self.specular_texture = Some(image);
+        self
+    }
+
+    pub fn to_uniforms(&self) -> uniforms::MaterialUniforms {
+        uniforms::MaterialUniforms::new(
+            self.ambient, self.diffuse, self.specular, self.shininess
+        )
+    }
+
+    /// Uploads the uniforms and textures. `white` stands in for missing
+    /// textures.
+    pub fn bind_group(
+        &self, device: &wgpu::Device, queue: &wgpu::Queue, layout: &wgpu::BindGroupLayout,
+        white: &texture::Texture
+    ) -> wgpu::BindGroup {
+        let diffuse = self.diffuse_texture.as_ref().map(|image| {
+            texture::Texture::from_image(device, queue, image, Some("Diffuse Texture"))
+        });
+        let specular = self.specular_texture.as_ref().map(|image| {
+            texture::Texture::from_image(device, queue, image, Some("Specular Texture"))
+        });
+        create_bind_group(
+            device, layout, bytemuck::bytes_of(&self.to_uniforms()),
+            diffuse.as_ref().unwrap_or(white), specular.as_ref().unwrap_or(white)
+        )
+    }
+}
+
+/// Metallic-roughness parameters as defined by glTF 2.0, shaded with a
+/// Cook-Torrance BRDF.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PbrMaterial {
+    /// Linear RGBA; the alpha is the output alpha.
+    pub base_color: [f32; 4],
+    /// 0 for dielectrics, 1 for metals.
+    pub metallic: f32,
+    /// Perceptual roughness, from 0 (mirror) to 1 (fully diffuse).
+    pub roughness: f32,
+    /// sRGB, multiplies `base_color`.
+    pub base_color_texture: Option<image::RgbaImage>,
+    /// Linear; roughness in the green and metalness in the blue channel
+    /// multiply `roughness` and `metallic`.
+    pub metallic_roughness_texture: Option<image::RgbaImage>,
+}
+
+impl PbrMaterial {
+    pub fn new(base_color: [f32; 3], metallic: f32, roughness: f32) -> Self {
+        let [r, g, b] = base_color;
+        Self {
+            base_color: [r, g, b, 1.0],
+            metallic,
+            roughness,
+            base_color_texture: None,
+            metallic_roughness_texture: None,
+        }
+    }
+
+    pub fn with_base_color_texture(mut self, image: image::RgbaImage) -> Self {
+        self.base_color_texture = Some(image);
+        self
+    }
+
+    pub fn with_metallic_roughness_texture(mut self, image: image::RgbaImage) -> Self {
+        self.metallic_roughness_texture = Some(image);
+        self
+    }
+
+    pub fn to_uniforms(&self) -> uniforms::PbrMaterialUniforms {
+        uniforms::PbrMaterialUniforms::new(self.base_color, self.metallic, self.roughness)
+    }
+
+    /// Like [`Material::bind_group`].
+    pub fn bind_group(
+        &self, device: &wgpu::Device, queue: &wgpu::Queue, layout: &wgpu::BindGroupLayout,
+        white: &texture::Texture
+    ) -> wgpu::BindGroup {
+        let base_color = self.base_color_texture.as_ref().map(|image| {
+            texture::Texture::from_image(device, queue, image, Some("Base Color Texture"))
+        });
+        let metallic_roughness = self.metallic_roughness_texture.as_ref().map(|image| {
+            texture::Texture::from_linear_image(
+                device, queue, image, Some("Metallic Roughness Texture")
+            )
+        });
+        create_bind_group(
+            device, layout, bytemuck::bytes_of(&self.to_uniforms()),
+            base_color.as_ref().unwrap_or(white), metallic_roughness.as_ref().unwrap_or(white)
+        )
+    }
+}
+
+/// The material of a [`crate::common::SceneObject`], which also picks the
+/// pipeline it is drawn with.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SurfaceMaterial {
+    BlinnPhong(Material),
+    Pbr(PbrMaterial),
+}
+
+impl SurfaceMaterial {
+    pub fn bind_group(
+        &self, device: &wgpu::Device, queue: &wgpu::Queue, layout: &wgpu::BindGroupLayout,
+        white: &texture::Texture
+    ) -> wgpu::BindGroup {
+        match self {
+            SurfaceMaterial::BlinnPhong(material) =>
+                material.bind_group(device, queue, layout, white),
+            SurfaceMaterial::Pbr(material) =>
+                material.bind_group(device, queue, layout, white),
+        }
+    }
+}
+
+impl From<Material> for SurfaceMaterial {
+    fn from(material: Material) -> Self {
+        SurfaceMaterial::BlinnPhong(material)
+    }
+}
+
+impl From<PbrMaterial> for SurfaceMaterial {
+    fn from(material: PbrMaterial) -> Self {
+        SurfaceMaterial::Pbr(material)
+    }
+}
+
+/// Layout of group 1, shared by both kinds of material.
+pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+        },
+        count: None,
+    };
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            texture_entry(1),
+            texture_entry(2),
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+        label: Some("Material Bind Group Layout"),
+    })
+}
+
+// the second texture is sampled with the first one's sampler
+fn create_bind_group(
+    device: &wgpu::Device, layout: &wgpu::BindGroupLayout, uniforms: &[u8],
+    first: &texture::Texture, second: &texture::Texture
+) -> wgpu::BindGroup {
+    use wgpu::util::DeviceExt;
+
+    let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Material Uniform Buffer"),
+        contents: uniforms,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&first.view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(&second.view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::Sampler(&first.sampler),
+            },
+        ],
+        label: Some("Material Bind Group"),
+    })
+}
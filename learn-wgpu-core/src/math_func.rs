@@ -0,0 +1,43 @@
+use cgmath::*;
+
+pub fn sphere_position(r: f32, theta: Deg<f32>, phi: Deg<f32>) -> [f32; 3] {
+    let x = r * theta.sin() * phi.cos();
+    let y = r * theta.cos();
+    let z = -r * theta.sin() * phi.sin();
+    [x, y, z]
+}
+
+pub fn torus_position(
+    r_torus: f32, r_tube: f32, u: Deg<f32>, v: Deg<f32>
+) -> [f32; 3] {
+    let x = (r_torus + r_tube * v.cos()) * u.cos();
+    let y = r_tube * v.sin();
+    let z = -(r_torus + r_tube * v.cos()) * u.sin();
+    [x, y, z]
+}
+
+/// Outward unit normal of the torus at `(u, v)`.
+pub fn torus_normal(u: Deg<f32>, v: Deg<f32>) -> [f32; 3] {
+    [v.cos() * u.cos(), v.sin(), -v.cos() * u.sin()]
+}
+
+pub fn cylinder_position(r: f32, u: Deg<f32>, y: f32) -> [f32; 3] {
+    [r * u.cos(), y, -r * u.sin()]
+}
+
+/// Cone with its base of radius `r` at `y = 0` and its apex at `y = h`.
+pub fn cone_position(r: f32, h: f32, u: Deg<f32>, y: f32) -> [f32; 3] {
+    let radius = r * (1.0 - y / h);
+    [radius * u.cos(), y, -radius * u.sin()]
+}
+
+/// Figure-8 immersion of the Klein bottle around the y axis; `a` is the
+/// radius of the ring the figure-8 cross section is swept along.
+pub fn klein_bottle_position(a: f32, u: Deg<f32>, v: Deg<f32>) -> [f32; 3] {
+    let half = u / 2.0;
+    let radius = a + half.cos() * v.sin() - half.sin() * (v * 2.0).sin();
+    let x = radius * u.cos();
+    let y = half.sin() * v.sin() + half.cos() * (v * 2.0).sin();
+    let z = -radius * u.sin();
+    [x, y, z]
+}
@@ -0,0 +1,162 @@
+//! Indexed triangle meshes: shared vertices plus a `u16` or `u32` index
+//! buffer, and welding of triangle soup into that form.
+
+use std::collections::HashMap;
+
+use crate::common::Vertex;
+
+/// Index buffer contents. `u16` is used whenever every index fits, halving
+/// the buffer size.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Indices {
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+impl Indices {
+    /// Picks the narrowest index type that can address all `indices`.
+    pub fn new(indices: Vec<u32>) -> Self {
+        if indices.iter().all(|&i| i <= u16::MAX as u32) {
+            Indices::U16(indices.into_iter().map(|i| i as u16).collect())
+        } else {
+            Indices::U32(indices)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Indices::U16(indices) => indices.len(),
+            Indices::U32(indices) => indices.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn format(&self) -> wgpu::IndexFormat {
+        match self {
+            Indices::U16(_) => wgpu::IndexFormat::Uint16,
+            Indices::U32(_) => wgpu::IndexFormat::Uint32,
+        }
+    }
+
+    /// Raw bytes for the index buffer, padded to a multiple of 4 as wgpu
+    /// requires for buffer writes.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = match self {
+            Indices::U16(indices) => bytemuck::cast_slice(indices).to_vec(),
+            Indices::U32(indices) => bytemuck::cast_slice(indices).to_vec(),
+        };
+        bytes.resize(bytes.len().next_multiple_of(4), 0);
+        bytes
+    }
+
+    pub fn iter(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+        match self {
+            Indices::U16(indices) => Box::new(indices.iter().map(|&i| i as u32)),
+            Indices::U32(indices) => Box::new(indices.iter().copied()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IndexedMesh {
+    pub vertices: Vec<Vertex>,
+    /// Every three indices form a triangle.
+    pub indices: Indices,
+}
+
+impl IndexedMesh {
+    /// Builds a mesh from the position/normal/index lists returned by the
+    /// `*_indexed` generators in [`crate::vertex_data`].
+    pub fn new(
+        positions: &[[f32; 3]], normals: &[[f32; 3]], indices: Vec<u32>
+    ) -> Self {
+        let vertices = positions
+            .iter()
+            .zip(normals)
+            .map(|(p, n)| Vertex {
+                position: [p[0], p[1], p[2], 1.0],
+                normal: [n[0], n[1], n[2], 1.0],
+            })
+            .collect();
+        Self { vertices, indices: Indices::new(indices) }
+    }
+
+    /// Expands the mesh back into a triangle list for `draw`.
+    pub fn to_soup(&self) -> Vec<Vertex> {
+        self.indices.iter().map(|i| self.vertices[i as usize]).collect()
+    }
+}
+
+/// Converts triangle soup into an indexed mesh by merging vertices whose
+/// position and normal are bitwise equal (`-0.0` counts as `0.0`).
+/// The triangle order is preserved.
+pub fn weld(soup: &[Vertex]) -> IndexedMesh {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::with_capacity(soup.len());
+    let mut lookup: HashMap<[u32; 8], u32> = HashMap::new();
+    for v in soup {
+        let mut key = [0u32; 8];
+        for (k, x) in key.iter_mut().zip(v.position.iter().chain(v.normal.iter())) {
+            // + 0.0 turns -0.0 into 0.0
+            *k = (x + 0.0).to_bits();
+        }
+        let index = *lookup.entry(key).or_insert_with(|| {
+            vertices.push(*v);
+            vertices.len() as u32 - 1
+        });
+        indices.push(index);
+    }
+    IndexedMesh { vertices, indices: Indices::new(indices) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vertex_data;
+
+    fn vertex(p: [f32; 3]) -> Vertex {
+        Vertex { position: [p[0], p[1], p[2], 1.0], normal: [0.0, 0.0, 1.0, 1.0] }
+    }
+
+    #[test]
+    fn weld_merges_shared_corners() {
+        // a quad as two triangles sharing an edge
+        let soup = [
+            vertex([0.0, 0.0, 0.0]), vertex([1.0, 0.0, 0.0]), vertex([1.0, 1.0, 0.0]),
+            vertex([1.0, 1.0, 0.0]), vertex([0.0, 1.0, 0.0]), vertex([-0.0, 0.0, 0.0]),
+        ];
+        let mesh = weld(&soup);
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.indices, Indices::U16(vec![0, 1, 2, 2, 3, 0]));
+        assert_eq!(
+            bytemuck::cast_slice::<_, u8>(&mesh.to_soup()),
+            bytemuck::cast_slice::<_, u8>(&[
+                soup[0], soup[1], soup[2], soup[3], soup[4], soup[0]
+            ])
+        );
+    }
+
+    #[test]
+    fn picks_index_width() {
+        assert_eq!(Indices::new(vec![0, 65535]).format(), wgpu::IndexFormat::Uint16);
+        assert_eq!(Indices::new(vec![0, 65536]).format(), wgpu::IndexFormat::Uint32);
+        // 3 u16 indices are padded to 8 bytes
+        assert_eq!(Indices::new(vec![0, 1, 2]).as_bytes().len(), 8);
+    }
+
+    #[test]
+    fn indexed_generators_share_vertices() {
+        let (positions, _, indices) = vertex_data::torus_indexed(1.5, 0.4, 180, 50);
+        assert_eq!(positions.len(), 180 * 50);
+        assert_eq!(indices.len(), 6 * 179 * 49);
+        assert!(indices.iter().all(|&i| (i as usize) < positions.len()));
+
+        let (positions, normals, indices) = vertex_data::cube_indexed();
+        let mesh = IndexedMesh::new(&positions, &normals, indices);
+        assert_eq!(mesh.vertices.len(), 24);
+        assert_eq!(weld(&mesh.to_soup()).vertices.len(), 24);
+    }
+}
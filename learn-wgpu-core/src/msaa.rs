@@ -0,0 +1,102 @@
+//! Multisample anti-aliasing: the scene is drawn into multisampled color
+//! and depth attachments, and the color is resolved into the surface.
+
+use std::fmt;
+
+/// Sample counts the renderer offers, if the formats support them.
+pub const SAMPLE_COUNTS: [u32; 4] = [1, 2, 4, 8];
+
+/// Features of `format` as the device validates them: the adapter's own
+/// with [`wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`],
+/// otherwise the ones WebGPU guarantees.
+pub fn format_features(
+    adapter: &wgpu::Adapter, device: &wgpu::Device, format: wgpu::TextureFormat
+) -> wgpu::TextureFormatFeatures {
+    let features = device.features();
+    if features.contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+        adapter.get_texture_format_features(format)
+    } else {
+        format.guaranteed_format_features(features)
+    }
+}
+
+/// The [`SAMPLE_COUNTS`] usable with a color target that is resolved and
+/// a depth buffer of the given formats.
+pub fn supported_sample_counts(
+    adapter: &wgpu::Adapter, device: &wgpu::Device,
+    color_format: wgpu::TextureFormat, depth_format: wgpu::TextureFormat
+) -> Vec<u32> {
+    let color = format_features(adapter, device, color_format).flags;
+    let depth = format_features(adapter, device, depth_format).flags;
+    SAMPLE_COUNTS
+        .into_iter()
+        .filter(|&count| {
+            count == 1 || (
+                color.sample_count_supported(count)
+                    && color.contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE)
+                    && depth.sample_count_supported(count)
+            )
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnsupportedSampleCount {
+    pub requested: u32,
+    pub supported: Vec<u32>,
+}
+
+impl fmt::Display for UnsupportedSampleCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f, "{}x MSAA is not supported for these formats, only {:?}",
+            self.requested, self.supported
+        )
+    }
+}
+
+impl std::error::Error for UnsupportedSampleCount {}
+
+/// The multisampled color attachment, resolved into the frame at the end
+/// of the pass.
+pub struct ColorBuffer {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+impl ColorBuffer {
+    pub fn new(
+        device: &wgpu::Device, width: u32, height: u32, format: wgpu::TextureFormat,
+        sample_count: u32
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Multisampled Color Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self { texture, view }
+    }
+
+    /// Recreates the texture if the size changed.
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        if (self.texture.width(), self.texture.height()) != (width, height) {
+            *self = Self::new(
+                device, width, height, self.texture.format(), self.texture.sample_count()
+            );
+        }
+    }
+
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+}
@@ -0,0 +1,456 @@
+//! Wavefront OBJ/MTL loading into the triangle lists used by the examples.
+//!
+//! Supports `v`, `vn`, `vt`, `f` (any polygon, triangulated as a fan, with
+//! `v`, `v/vt`, `v//vn` and `v/vt/vn` corners and negative indices),
+//! `usemtl` and `mtllib`. Faces without normals get flat or smooth normals
+//! generated according to [`NormalMode`].
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use cgmath::*;
+
+use crate::common;
+
+/// How normals are generated for faces that do not specify them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NormalMode {
+    /// One normal per triangle.
+    Flat,
+    /// Area-weighted average of the faces sharing a position.
+    Smooth,
+}
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(std::io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(e) => write!(f, "{}", e),
+            ObjError::Parse { line, message } =>
+                write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+impl From<std::io::Error> for ObjError {
+    fn from(e: std::io::Error) -> Self {
+        ObjError::Io(e)
+    }
+}
+
+/// Surface properties read from an MTL file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjMaterial {
+    pub name: String,
+    pub ambient: [f32; 3],
+    pub diffuse: [f32; 3],
+    pub specular: [f32; 3],
+    pub shininess: f32,
+}
+
+impl ObjMaterial {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ambient: [0.0, 0.0, 0.0],
+            diffuse: [0.8, 0.8, 0.8],
+            specular: [0.0, 0.0, 0.0],
+            shininess: 30.0,
+        }
+    }
+}
+
+/// Triangle soup for the faces sharing one material: every three entries
+/// of `positions`/`normals`/`uvs` form a triangle.
+#[derive(Clone, Debug, Default)]
+pub struct ObjMesh {
+    pub material: Option<String>,
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    /// Texture coordinates, `[0.0, 0.0]` for corners without one.
+    pub uvs: Vec<[f32; 2]>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ObjModel {
+    pub meshes: Vec<ObjMesh>,
+    pub materials: Vec<ObjMaterial>,
+}
+
+impl ObjModel {
+    /// All meshes as vertices for [`common::run`].
+    pub fn vertices(&self) -> Vec<common::Vertex> {
+        self.meshes
+            .iter()
+            .flat_map(|mesh| mesh.positions.iter().zip(mesh.normals.iter()))
+            .map(|(p, n)| common::Vertex {
+                position: [p[0], p[1], p[2], 1.0],
+                normal: [n[0], n[1], n[2], 1.0],
+            })
+            .collect()
+    }
+
+    pub fn material(&self, name: &str) -> Option<&ObjMaterial> {
+        self.materials.iter().find(|m| m.name == name)
+    }
+}
+
+/// Loads an OBJ file and the MTL libraries it references, resolved relative
+/// to the OBJ file.
+pub fn load_obj(
+    path: impl AsRef<Path>, normal_mode: NormalMode
+) -> Result<ObjModel, ObjError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)?;
+    let mut model = parse_obj(&source, normal_mode)?;
+
+    let dir = path.parent().unwrap_or(Path::new(""));
+    for line in source.lines() {
+        let mut tokens = line.split_whitespace();
+        if tokens.next() == Some("mtllib") {
+            for lib in tokens {
+                let mtl = fs::read_to_string(dir.join(lib))?;
+                model.materials.extend(parse_mtl(&mtl)?);
+            }
+        }
+    }
+
+    Ok(model)
+}
+
+/// A face corner as indices into the position, texcoord and normal lists.
+#[derive(Copy, Clone)]
+struct Corner {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+struct Face {
+    corners: [Corner; 3],
+    mesh: usize,
+}
+
+/// Parses OBJ source. `mtllib` statements are ignored, see [`load_obj`].
+pub fn parse_obj(
+    source: &str, normal_mode: NormalMode
+) -> Result<ObjModel, ObjError> {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut faces: Vec<Face> = Vec::new();
+    let mut meshes = vec![ObjMesh::default()];
+
+    for (i, line) in source.lines().enumerate() {
+        let line_no = i + 1;
+        let line = line.split('#').next().unwrap_or("");
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else { continue };
+        let args: Vec<&str> = tokens.collect();
+        match keyword {
+            "v" => positions.push(parse_floats(&args, line_no)?),
+            "vn" => normals.push(parse_floats(&args, line_no)?),
+            "vt" => {
+                // v defaults to 0 and the optional w is ignored
+                let uv = match args.len() {
+                    1 => {
+                        let [u] = parse_floats(&args, line_no)?;
+                        [u, 0.0]
+                    },
+                    2 => parse_floats(&args, line_no)?,
+                    3 => {
+                        let [u, v, _] = parse_floats(&args, line_no)?;
+                        [u, v]
+                    },
+                    _ => return Err(parse_error(line_no, "expected 1 to 3 numbers")),
+                };
+                uvs.push(uv);
+            },
+            "f" => {
+                if args.len() < 3 {
+                    return Err(parse_error(line_no, "face needs at least 3 vertices"));
+                }
+                let corners = args
+                    .iter()
+                    .map(|arg| parse_corner(
+                        arg, positions.len(), uvs.len(), normals.len(), line_no
+                    ))
+                    .collect::<Result<Vec<_>, _>>()?;
+                // triangulate as a fan around the first corner
+                for k in 1..corners.len() - 1 {
+                    faces.push(Face {
+                        corners: [corners[0], corners[k], corners[k + 1]],
+                        mesh: meshes.len() - 1,
+                    });
+                }
+            },
+            "usemtl" => {
+                let name = args.first().map(|s| s.to_string());
+                let current = meshes.len() - 1;
+                if faces.last().is_none_or(|f| f.mesh != current) {
+                    // nothing emitted for the current group yet, reuse it
+                    meshes[current].material = name;
+                } else {
+                    meshes.push(ObjMesh { material: name, ..Default::default() });
+                }
+            },
+            _ => {}
+        }
+    }
+
+    let smooth_normals = match normal_mode {
+        NormalMode::Smooth => smooth_normals(&positions, &faces),
+        NormalMode::Flat => Vec::new(),
+    };
+
+    for face in &faces {
+        let p = face.corners.map(|c| positions[c.position]);
+        let face_normal = triangle_normal(p[0], p[1], p[2]);
+        let has_normals = face.corners.iter().all(|c| c.normal.is_some());
+        let mesh = &mut meshes[face.mesh];
+        for (corner, position) in face.corners.iter().zip(p) {
+            let normal = match (has_normals, normal_mode) {
+                (true, _) => normals[corner.normal.unwrap()],
+                (false, NormalMode::Flat) => face_normal,
+                (false, NormalMode::Smooth) => smooth_normals[corner.position],
+            };
+            mesh.positions.push(position);
+            mesh.normals.push(normal);
+            mesh.uvs.push(corner.uv.map_or([0.0, 0.0], |i| uvs[i]));
+        }
+    }
+
+    meshes.retain(|mesh| !mesh.positions.is_empty());
+    Ok(ObjModel { meshes, materials: Vec::new() })
+}
+
+/// Parses MTL source. Only the Phong terms (`Ka`, `Kd`, `Ks`, `Ns`) are read.
+pub fn parse_mtl(source: &str) -> Result<Vec<ObjMaterial>, ObjError> {
+    let mut materials: Vec<ObjMaterial> = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        let line_no = i + 1;
+        let line = line.split('#').next().unwrap_or("");
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else { continue };
+        let args: Vec<&str> = tokens.collect();
+        if keyword == "newmtl" {
+            let name = args.first()
+                .ok_or_else(|| parse_error(line_no, "newmtl needs a name"))?;
+            materials.push(ObjMaterial::new(name));
+            continue;
+        }
+        let Some(material) = materials.last_mut() else { continue };
+        match keyword {
+            "Ka" => material.ambient = parse_floats(&args, line_no)?,
+            "Kd" => material.diffuse = parse_floats(&args, line_no)?,
+            "Ks" => material.specular = parse_floats(&args, line_no)?,
+            "Ns" => material.shininess = parse_floats::<1>(&args, line_no)?[0],
+            _ => {}
+        }
+    }
+    Ok(materials)
+}
+
+fn parse_error(line: usize, message: &str) -> ObjError {
+    ObjError::Parse { line, message: message.to_string() }
+}
+
+/// Parses the first `N` arguments as floats; extra components such as the
+/// optional `w` of `v` are ignored.
+fn parse_floats<const N: usize>(
+    args: &[&str], line: usize
+) -> Result<[f32; N], ObjError> {
+    if args.len() < N {
+        return Err(parse_error(line, &format!("expected {} numbers", N)));
+    }
+    let mut out = [0.0; N];
+    for (value, arg) in out.iter_mut().zip(args) {
+        *value = arg
+            .parse()
+            .map_err(|_| parse_error(line, &format!("invalid number '{}'", arg)))?;
+    }
+    Ok(out)
+}
+
+fn parse_corner(
+    arg: &str, num_positions: usize, num_uvs: usize, num_normals: usize,
+    line: usize
+) -> Result<Corner, ObjError> {
+    let mut parts = arg.split('/');
+    let position = resolve_index(parts.next(), num_positions, line)?
+        .ok_or_else(|| parse_error(line, "face vertex needs a position"))?;
+    let uv = resolve_index(parts.next(), num_uvs, line)?;
+    let normal = resolve_index(parts.next(), num_normals, line)?;
+    Ok(Corner { position, uv, normal })
+}
+
+/// Converts a 1-based (or negative, relative) OBJ index to a 0-based one.
+fn resolve_index(
+    part: Option<&str>, len: usize, line: usize
+) -> Result<Option<usize>, ObjError> {
+    let part = match part {
+        Some(p) if !p.is_empty() => p,
+        _ => return Ok(None),
+    };
+    let index: i64 = part
+        .parse()
+        .map_err(|_| parse_error(line, &format!("invalid index '{}'", part)))?;
+    let resolved = if index > 0 { index - 1 } else { len as i64 + index };
+    if index == 0 || resolved < 0 || resolved >= len as i64 {
+        return Err(parse_error(line, &format!("index {} out of range", index)));
+    }
+    Ok(Some(resolved as usize))
+}
+
+fn triangle_normal(p0: [f32; 3], p1: [f32; 3], p2: [f32; 3]) -> [f32; 3] {
+    let n = area_normal(p0, p1, p2);
+    if n.magnitude2() > 0.0 { n.normalize().into() } else { [0.0, 0.0, 0.0] }
+}
+
+/// Cross product of two triangle edges; its length is twice the area.
+fn area_normal(p0: [f32; 3], p1: [f32; 3], p2: [f32; 3]) -> Vector3<f32> {
+    let p0 = Vector3::from(p0);
+    (Vector3::from(p1) - p0).cross(Vector3::from(p2) - p0)
+}
+
+fn smooth_normals(positions: &[[f32; 3]], faces: &[Face]) -> Vec<[f32; 3]> {
+    let mut sums: HashMap<usize, Vector3<f32>> = HashMap::new();
+    for face in faces {
+        let [a, b, c] = face.corners.map(|c| c.position);
+        let n = area_normal(positions[a], positions[b], positions[c]);
+        for index in [a, b, c] {
+            *sums.entry(index).or_insert(Vector3::zero()) += n;
+        }
+    }
+    (0..positions.len())
+        .map(|i| match sums.get(&i) {
+            Some(n) if n.magnitude2() > 0.0 => n.normalize().into(),
+            _ => [0.0, 0.0, 0.0],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUAD: &str = "
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        f 1 2 3 4
+    ";
+
+    #[test]
+    fn triangulates_polygons_as_fan() {
+        let model = parse_obj(QUAD, NormalMode::Flat).unwrap();
+        let mesh = &model.meshes[0];
+        assert_eq!(mesh.positions, vec![
+            [0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0],
+        ]);
+        assert!(mesh.normals.iter().all(|n| *n == [0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn reads_texcoords_normals_and_negative_indices() {
+        let source = "
+            v 0 0 0
+            v 1 0 0
+            v 0 1 0
+            vt 0.5 1
+            vn 0 0 -1
+            f -3/1/1 -2/1/1 -1//1
+        ";
+        let mesh = &parse_obj(source, NormalMode::Smooth).unwrap().meshes[0];
+        assert_eq!(mesh.positions[2], [0.0, 1.0, 0.0]);
+        assert_eq!(mesh.uvs, vec![[0.5, 1.0], [0.5, 1.0], [0.0, 0.0]]);
+        assert!(mesh.normals.iter().all(|n| *n == [0.0, 0.0, -1.0]));
+    }
+
+    #[test]
+    fn texcoords_take_one_to_three_components() {
+        let source = "
+            v 0 0 0
+            v 1 0 0
+            v 0 1 0
+            vt 0.25
+            vt 0.5 0.75
+            vt 1 0.5 0
+            f 1/1 2/2 3/3
+        ";
+        let mesh = &parse_obj(source, NormalMode::Flat).unwrap().meshes[0];
+        assert_eq!(mesh.uvs, vec![[0.25, 0.0], [0.5, 0.75], [1.0, 0.5]]);
+
+        for line in ["vt", "vt 0 0 0 0", "vt 0 x"] {
+            let err = parse_obj(line, NormalMode::Flat).unwrap_err();
+            assert!(matches!(err, ObjError::Parse { line: 1, .. }), "{}", line);
+        }
+    }
+
+    #[test]
+    fn smooth_normals_average_adjacent_faces() {
+        // two faces folded 90 degrees along the shared edge x = 0
+        let source = "
+            v 0 0 0
+            v 0 1 0
+            v 1 0 0
+            v 0 0 1
+            f 1 3 2
+            f 1 2 4
+        ";
+        let mesh = &parse_obj(source, NormalMode::Smooth).unwrap().meshes[0];
+        let s = 0.5f32.sqrt();
+        let n = mesh.normals[0];
+        assert!((n[0] - s).abs() < 1e-6 && n[1].abs() < 1e-6 && (n[2] - s).abs() < 1e-6);
+        assert_eq!(mesh.normals[1], [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn splits_meshes_by_material() {
+        let source = "
+            v 0 0 0
+            v 1 0 0
+            v 0 1 0
+            usemtl red
+            f 1 2 3
+            usemtl blue
+            f 3 2 1
+        ";
+        let model = parse_obj(source, NormalMode::Flat).unwrap();
+        let names: Vec<_> = model.meshes.iter()
+            .map(|m| m.material.as_deref())
+            .collect();
+        assert_eq!(names, vec![Some("red"), Some("blue")]);
+    }
+
+    #[test]
+    fn reports_line_of_invalid_index() {
+        let err = parse_obj("v 0 0 0\nf 1 2 3\n", NormalMode::Flat).unwrap_err();
+        assert!(matches!(err, ObjError::Parse { line: 2, .. }));
+    }
+
+    #[test]
+    fn parses_mtl_phong_terms() {
+        let materials = parse_mtl("
+            newmtl shiny
+            Kd 1 0 0
+            Ks 0.5 0.5 0.5
+            Ns 64
+        ").unwrap();
+        assert_eq!(materials[0].name, "shiny");
+        assert_eq!(materials[0].diffuse, [1.0, 0.0, 0.0]);
+        assert_eq!(materials[0].specular, [0.5, 0.5, 0.5]);
+        assert_eq!(materials[0].shininess, 64.0);
+    }
+}
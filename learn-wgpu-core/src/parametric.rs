@@ -0,0 +1,254 @@
+//! Indexed meshes for parametric surfaces `(u, v) -> position` sampled on a
+//! regular grid over a rectangular UV domain, plus presets for common
+//! shapes built from the position functions in [`crate::math_func`].
+//!
+//! Triangles are wound counter-clockwise around `dP/du x dP/dv`, which is
+//! also the direction of finite-difference normals.
+
+use cgmath::*;
+
+use crate::{math_func, mesh};
+
+/// How vertex normals are computed.
+pub enum Normals<'a> {
+    /// Exact unit normal at `(u, v)`.
+    Analytic(&'a dyn Fn(f32, f32) -> [f32; 3]),
+    /// Cross product of central differences along u and v. Where the surface
+    /// is degenerate (poles, apexes) the point is nudged into the domain.
+    FiniteDifference,
+}
+
+pub struct SurfaceData {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    /// `(u, v)` mapped to `[0, 1]` over the domain.
+    pub uvs: Vec<[f32; 2]>,
+    pub indices: Vec<u32>,
+}
+
+impl SurfaceData {
+    pub fn mesh(&self) -> mesh::IndexedMesh {
+        mesh::IndexedMesh::new(&self.positions, &self.normals, self.indices.clone())
+    }
+}
+
+/// Samples `position` on `nu` x `nv` vertices spanning `u_range` and
+/// `v_range` (both ends included, so closed surfaces repeat their seam).
+pub fn parametric_surface(
+    position: impl Fn(f32, f32) -> [f32; 3], normals: Normals,
+    u_range: [f32; 2], v_range: [f32; 2], nu: usize, nv: usize
+) -> SurfaceData {
+    assert!(nu >= 2 && nv >= 2, "a surface needs at least 2x2 vertices");
+    let capacity = nu * nv;
+    let mut data = SurfaceData {
+        positions: Vec::with_capacity(capacity),
+        normals: Vec::with_capacity(capacity),
+        uvs: Vec::with_capacity(capacity),
+        indices: Vec::with_capacity(6 * (nu - 1) * (nv - 1)),
+    };
+
+    for i in 0..nu {
+        for j in 0..nv {
+            let s = i as f32 / (nu as f32 - 1.0);
+            let t = j as f32 / (nv as f32 - 1.0);
+            let u = u_range[0] + s * (u_range[1] - u_range[0]);
+            let v = v_range[0] + t * (v_range[1] - v_range[0]);
+            data.positions.push(position(u, v));
+            data.normals.push(match normals {
+                Normals::Analytic(normal) => normal(u, v),
+                Normals::FiniteDifference =>
+                    finite_difference_normal(&position, u, v, u_range, v_range),
+            });
+            data.uvs.push([s, t]);
+        }
+    }
+
+    for i in 0..nu - 1 {
+        for j in 0..nv - 1 {
+            let i0 = (i * nv + j) as u32;
+            let i1 = ((i + 1) * nv + j) as u32;
+            let i2 = ((i + 1) * nv + j + 1) as u32;
+            let i3 = (i * nv + j + 1) as u32;
+            data.indices.extend_from_slice(&[i0, i1, i2, i2, i3, i0]);
+        }
+    }
+
+    data
+}
+
+fn finite_difference_normal(
+    position: &impl Fn(f32, f32) -> [f32; 3], u: f32, v: f32,
+    u_range: [f32; 2], v_range: [f32; 2]
+) -> [f32; 3] {
+    let hu = 1e-4 * (u_range[1] - u_range[0]);
+    let hv = 1e-4 * (v_range[1] - v_range[0]);
+    let center = [
+        0.5 * (u_range[0] + u_range[1]),
+        0.5 * (v_range[0] + v_range[1]),
+    ];
+    // samples stay inside the domain, so differences are one-sided at its edges
+    let clamp_u = |u: f32| u.clamp(u_range[0].min(u_range[1]), u_range[0].max(u_range[1]));
+    let clamp_v = |v: f32| v.clamp(v_range[0].min(v_range[1]), v_range[0].max(v_range[1]));
+    for nudge in [0.0, 1e-3, 1e-2] {
+        let u = u + nudge * (center[0] - u);
+        let v = v + nudge * (center[1] - v);
+        let du = Vector3::from(position(clamp_u(u + hu), v))
+            - Vector3::from(position(clamp_u(u - hu), v));
+        let dv = Vector3::from(position(u, clamp_v(v + hv)))
+            - Vector3::from(position(u, clamp_v(v - hv)));
+        // a derivative that (nearly) vanishes relative to the other one is
+        // rounding noise, e.g. at a pole
+        let (lu, lv) = (du.magnitude(), dv.magnitude());
+        let n = du.cross(dv);
+        if lu.min(lv) > 1e-3 * lu.max(lv) && n.magnitude() > 1e-6 * lu * lv {
+            return n.normalize().into();
+        }
+    }
+    [0.0, 0.0, 0.0]
+}
+
+/// Sphere of radius `r`; u runs from the north to the south pole and v
+/// around the y axis.
+pub fn sphere(r: f32, nu: usize, nv: usize) -> SurfaceData {
+    let position = |u, v| math_func::sphere_position(r, Deg(u), Deg(v));
+    let normal = |u, v| {
+        let p = math_func::sphere_position(1.0, Deg(u), Deg(v));
+        [p[0], p[1], p[2]]
+    };
+    parametric_surface(
+        position, Normals::Analytic(&normal), [0.0, 180.0], [0.0, 360.0], nu, nv
+    )
+}
+
+/// Torus around the y axis; u runs around the ring and v around the tube.
+pub fn torus(r_torus: f32, r_tube: f32, n_torus: usize, n_tube: usize) -> SurfaceData {
+    let position = |u, v| math_func::torus_position(r_torus, r_tube, Deg(u), Deg(v));
+    let normal = |u, v| math_func::torus_normal(Deg(u), Deg(v));
+    parametric_surface(
+        position, Normals::Analytic(&normal), [0.0, 360.0], [0.0, 360.0],
+        n_torus, n_tube
+    )
+}
+
+/// Open cylinder of radius `r` and height `h` centered on the origin.
+pub fn cylinder(r: f32, h: f32, nu: usize, nv: usize) -> SurfaceData {
+    let position = |u, y| math_func::cylinder_position(r, Deg(u), y);
+    let normal = |u, _| math_func::cylinder_position(1.0, Deg(u), 0.0);
+    parametric_surface(
+        position, Normals::Analytic(&normal), [0.0, 360.0], [-0.5 * h, 0.5 * h],
+        nu, nv
+    )
+}
+
+/// Open cone with base radius `r` at `y = -h/2` and its apex at `y = h/2`.
+pub fn cone(r: f32, h: f32, nu: usize, nv: usize) -> SurfaceData {
+    let position = |u, y| {
+        let p = math_func::cone_position(r, h, Deg(u), y);
+        [p[0], p[1] - 0.5 * h, p[2]]
+    };
+    let normal = |u: f32, _| {
+        let n = Vector3::new(h * Deg(u).cos(), r, -h * Deg(u).sin());
+        n.normalize().into()
+    };
+    parametric_surface(
+        position, Normals::Analytic(&normal), [0.0, 360.0], [0.0, h], nu, nv
+    )
+}
+
+/// Figure-8 Klein bottle; being non-orientable, its normals flip sign
+/// across the u seam.
+pub fn klein_bottle(a: f32, nu: usize, nv: usize) -> SurfaceData {
+    let position = |u, v| math_func::klein_bottle_position(a, Deg(u), Deg(v));
+    parametric_surface(
+        position, Normals::FiniteDifference, [0.0, 360.0], [0.0, 360.0], nu, nv
+    )
+}
+
+/// Sweeps `profile(t) = [radius, y]` around the y axis; u is the angle and
+/// v runs over `t_range`. Normals face outwards where y grows with t.
+pub fn revolution(
+    profile: impl Fn(f32) -> [f32; 2], t_range: [f32; 2], nu: usize, nv: usize
+) -> SurfaceData {
+    let position = |u, t| {
+        let [radius, y] = profile(t);
+        math_func::cylinder_position(radius, Deg(u), y)
+    };
+    parametric_surface(
+        position, Normals::FiniteDifference, [0.0, 360.0], t_range, nu, nv
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: [f32; 3], b: [f32; 3], eps: f32) {
+        for (x, y) in a.iter().zip(b) {
+            assert!((x - y).abs() < eps, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn torus_points_lie_on_tube() {
+        let (r_torus, r_tube) = (1.5, 0.4);
+        let torus = torus(r_torus, r_tube, 36, 18);
+        for (p, n) in torus.positions.iter().zip(&torus.normals) {
+            // nearest point on the ring the tube is swept along
+            let radial = Vector2::new(p[0], p[2]).normalize() * r_torus;
+            let center = Vector3::new(radial.x, 0.0, radial.y);
+            let offset = Vector3::from(*p) - center;
+            assert!((offset.magnitude() - r_tube).abs() < 1e-5, "{:?}", p);
+            // the normal points from the tube center through the point
+            assert_near((offset / r_tube).into(), *n, 1e-5);
+        }
+    }
+
+    #[test]
+    fn grid_layout_and_uvs() {
+        let sphere = sphere(2.0, 15, 20);
+        assert_eq!(sphere.positions.len(), 15 * 20);
+        assert_eq!(sphere.indices.len(), 6 * 14 * 19);
+        assert_eq!(sphere.uvs[0], [0.0, 0.0]);
+        assert_eq!(*sphere.uvs.last().unwrap(), [1.0, 1.0]);
+        assert!(sphere.indices.iter().all(|&i| (i as usize) < sphere.positions.len()));
+    }
+
+    #[test]
+    fn finite_differences_match_analytic_normals() {
+        let analytic = sphere(2.0, 9, 13);
+        let position = |u, v| math_func::sphere_position(2.0, Deg(u), Deg(v));
+        let numeric = parametric_surface(
+            position, Normals::FiniteDifference, [0.0, 180.0], [0.0, 360.0], 9, 13
+        );
+        // includes the poles, where the nudge kicks in
+        for (a, n) in analytic.normals.iter().zip(&numeric.normals) {
+            assert_near(*a, *n, 1e-2);
+        }
+
+        let analytic = cone(1.0, 2.0, 8, 4);
+        let numeric = revolution(|t| [1.0 - 0.5 * t, t - 1.0], [0.0, 2.0], 8, 4);
+        for (a, n) in analytic.normals.iter().zip(&numeric.normals) {
+            assert_near(*a, *n, 1e-2);
+        }
+    }
+
+    #[test]
+    fn revolution_of_constant_profile_is_cylinder() {
+        let a = cylinder(1.0, 2.0, 12, 3);
+        let b = revolution(|t| [1.0, t], [-1.0, 1.0], 12, 3);
+        for (a, b) in a.positions.iter().zip(&b.positions) {
+            assert_near(*a, *b, 1e-6);
+        }
+        for (a, b) in a.normals.iter().zip(&b.normals) {
+            assert_near(*a, *b, 1e-3);
+        }
+    }
+
+    #[test]
+    fn klein_bottle_has_unit_normals() {
+        let klein = klein_bottle(2.0, 24, 16);
+        for n in &klein.normals {
+            assert!((Vector3::from(*n).magnitude() - 1.0).abs() < 1e-4, "{:?}", n);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+// metallic-roughness material, appended to common.wgsl
+
+struct PbrMaterial {
+    base_color : vec4<f32>,
+    metallic : f32,
+    roughness : f32,
+};
+
+@binding(0) @group(1) var<uniform> material : PbrMaterial;
+// white when the material has no texture
+@binding(1) @group(1) var base_color_texture : texture_2d<f32>;
+// roughness in green, metalness in blue
+@binding(2) @group(1) var metallic_roughness_texture : texture_2d<f32>;
+@binding(3) @group(1) var material_sampler : sampler;
+
+// false when rendering to a non-sRGB target, which then gets the sRGB
+// transfer function applied here
+override SRGB_TARGET : bool = true;
+
+const PI : f32 = 3.14159265;
+// constant environment light, as a fraction of the base color
+const AMBIENT : f32 = 0.03;
+
+// GGX / Trowbridge-Reitz normal distribution
+fn distribution_ggx(n_dot_h : f32, alpha : f32) -> f32 {
+    let a2 : f32 = alpha * alpha;
+    let d : f32 = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+// Smith's shadowing-masking with the Schlick-GGX approximation for direct
+// lights
+fn geometry_smith(n_dot_v : f32, n_dot_l : f32, roughness : f32) -> f32 {
+    let r : f32 = roughness + 1.0;
+    let k : f32 = r * r / 8.0;
+    let g_v : f32 = n_dot_v / (n_dot_v * (1.0 - k) + k);
+    let g_l : f32 = n_dot_l / (n_dot_l * (1.0 - k) + k);
+    return g_v * g_l;
+}
+
+fn fresnel_schlick(cos_theta : f32, f0 : vec3<f32>) -> vec3<f32> {
+    return f0 + (1.0 - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+fn linear_to_srgb(c : vec3<f32>) -> vec3<f32> {
+    let low : vec3<f32> = c * 12.92;
+    let high : vec3<f32> = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, c <= vec3<f32>(0.0031308));
+}
+
+@fragment
+fn fs_main(
+    @location(0) v_position : vec4<f32>,
+    @location(1) v_normal : vec4<f32>,
+    @location(2) v_uv : vec2<f32>,
+    @location(3) v_color : vec4<f32>
+) -> @location(0) vec4<f32> {
+    let base_color : vec4<f32> = v_color * material.base_color *
+        textureSample(base_color_texture, material_sampler, v_uv);
+    let metallic_roughness : vec4<f32> =
+        textureSample(metallic_roughness_texture, material_sampler, v_uv);
+    let metallic : f32 = clamp(material.metallic * metallic_roughness.b, 0.0, 1.0);
+    // very smooth surfaces would turn point lights into invisible dots
+    let roughness : f32 = clamp(material.roughness * metallic_roughness.g, 0.04, 1.0);
+    let alpha : f32 = roughness * roughness;
+    // dielectrics reflect about 4% head-on, metals their base color
+    let f0 : vec3<f32> = mix(vec3<f32>(0.04), base_color.rgb, metallic);
+
+    let N : vec3<f32> = normalize(v_normal.xyz);
+    let V : vec3<f32> =
+        normalize(frag_uniforms.eye_position.xyz - v_position.xyz);
+    let n_dot_v : f32 = max(dot(N, V), 1e-4);
+    var radiance_out : vec3<f32> = vec3<f32>(0.0);
+    for (var i : u32 = 0u; i < min(frag_uniforms.light_count, MAX_LIGHTS); i++) {
+        let light : Incident = incident(frag_uniforms.lights[i], v_position.xyz);
+        let H : vec3<f32> = normalize(light.L + V);
+        let n_dot_l : f32 = max(dot(N, light.L), 0.0);
+        let n_dot_h : f32 = max(dot(N, H), 0.0);
+
+        let F : vec3<f32> = fresnel_schlick(max(dot(H, V), 0.0), f0);
+        let specular : vec3<f32> =
+            distribution_ggx(n_dot_h, alpha) * geometry_smith(n_dot_v, n_dot_l, roughness) * F
+            / (4.0 * n_dot_v * max(n_dot_l, 1e-4));
+        // light that is not reflected is refracted and scattered, except
+        // by metals which absorb it
+        let k_d : vec3<f32> = (1.0 - F) * (1.0 - metallic);
+        radiance_out += (k_d * base_color.rgb / PI + specular) * light.radiance * n_dot_l;
+    }
+    var color : vec3<f32> = AMBIENT * base_color.rgb + radiance_out;
+    if (!SRGB_TARGET) {
+        color = linear_to_srgb(clamp(color, vec3<f32>(0.0), vec3<f32>(1.0)));
+    }
+    return vec4<f32>(color, base_color.a);
+}
@@ -0,0 +1,74 @@
+use std::sync::mpsc;
+
+const BYTES_PER_PIXEL: u32 = 4;
+
+/// Copies a 2D texture with 4-byte texels (RGBA8/BGRA8) back to the CPU and
+/// returns its rows tightly packed, top row first. The texture must have been
+/// created with `TextureUsages::COPY_SRC`.
+pub fn read_texture(
+    device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture
+) -> Vec<u8> {
+    let width = texture.width();
+    let height = texture.height();
+    let unpadded_bytes_per_row = width * BYTES_PER_PIXEL;
+    let padded_bytes_per_row =
+        unpadded_bytes_per_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback Buffer"),
+        size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(
+        &wgpu::CommandEncoderDescriptor { label: Some("Readback Encoder") }
+    );
+    encoder.copy_texture_to_buffer(
+        wgpu::TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+    queue.submit(Some(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device
+        .poll(wgpu::PollType::wait_indefinitely())
+        .expect("Failed to wait for readback");
+    receiver
+        .recv()
+        .expect("Readback callback was dropped")
+        .expect("Failed to map readback buffer");
+
+    let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+    {
+        let data = slice.get_mapped_range();
+        for row in data.chunks(padded_bytes_per_row as usize) {
+            pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+        }
+    }
+    buffer.unmap();
+
+    pixels
+}
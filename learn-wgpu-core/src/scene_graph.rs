@@ -0,0 +1,272 @@
+//! A hierarchy of nodes, each placed by a translation, rotation and scale
+//! relative to its parent. Nodes may draw a mesh with a material; meshes
+//! and materials are stored once and shared by any number of nodes.
+//!
+//! World matrices are only recomputed for nodes whose transform, or whose
+//! ancestor's transform, changed since the last
+//! [`SceneGraph::update_world_matrices`].
+
+use std::time::Duration;
+
+use cgmath::{Matrix4, Quaternion, SquareMatrix};
+
+use crate::common::{SceneObject, TexturedVertex};
+use crate::material::SurfaceMaterial;
+use crate::mesh;
+use crate::transforms::Transform;
+
+/// Moves the nodes of the graph on every [`crate::common::State::update`],
+/// given the time since the start.
+pub type GraphAnimation = Box<dyn FnMut(&mut SceneGraph, Duration)>;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(pub(crate) usize);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MeshId(pub(crate) usize);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MaterialId(pub(crate) usize);
+
+/// Geometry in the vertex format [`crate::common::State`] draws.
+#[derive(Clone, Debug)]
+pub struct Mesh {
+    pub vertex_data: Vec<TexturedVertex>,
+    /// Drawn with `draw_indexed` when set, otherwise `vertex_data` is a
+    /// triangle list.
+    pub indices: Option<mesh::Indices>,
+}
+
+impl Mesh {
+    pub fn textured(vertex_data: &[TexturedVertex]) -> Self {
+        Self { vertex_data: vertex_data.to_vec(), indices: None }
+    }
+
+    pub fn indexed(mesh: &mesh::IndexedMesh) -> Self {
+        Self {
+            vertex_data: mesh.vertices.iter().map(|&v| v.into()).collect(),
+            indices: Some(mesh.indices.clone()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub name: String,
+    transform: Transform,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    mesh: Option<(MeshId, MaterialId)>,
+    world_mat: Matrix4<f32>,
+    // the local transform changed since the world matrix was computed
+    dirty: bool,
+}
+
+impl Node {
+    /// Relative to the parent.
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    pub fn mesh(&self) -> Option<(MeshId, MaterialId)> {
+        self.mesh
+    }
+
+    pub fn local_matrix(&self) -> Matrix4<f32> {
+        self.transform.to_matrix()
+    }
+
+    /// As of the last [`SceneGraph::update_world_matrices`].
+    pub fn world_matrix(&self) -> Matrix4<f32> {
+        self.world_mat
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SceneGraph {
+    // parents always precede their children
+    nodes: Vec<Node>,
+    meshes: Vec<Mesh>,
+    materials: Vec<SurfaceMaterial>,
+}
+
+impl SceneGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// One root node drawing each object, in order. A node keeps the
+    /// object's model matrix until its transform is set.
+    pub fn from_objects(objects: &[SceneObject]) -> Self {
+        let mut graph = Self::new();
+        for (i, object) in objects.iter().enumerate() {
+            let mesh = graph.add_mesh(Mesh {
+                vertex_data: object.vertex_data.clone(),
+                indices: object.indices.clone(),
+            });
+            let material = graph.add_material(object.material.clone());
+            let node = graph.add_node(None, format!("object {}", i));
+            graph.set_mesh(node, mesh, material);
+            // an arbitrary matrix may not split into translation, rotation
+            // and scale
+            let node = &mut graph.nodes[node.0];
+            node.world_mat = object.model_mat;
+            node.dirty = false;
+        }
+        graph
+    }
+
+    pub fn add_mesh(&mut self, mesh: Mesh) -> MeshId {
+        self.meshes.push(mesh);
+        MeshId(self.meshes.len() - 1)
+    }
+
+    pub fn add_material(&mut self, material: impl Into<SurfaceMaterial>) -> MaterialId {
+        self.materials.push(material.into());
+        MaterialId(self.materials.len() - 1)
+    }
+
+    /// Adds a node with the identity transform, as a root or as the last
+    /// child of `parent`.
+    pub fn add_node(&mut self, parent: Option<NodeId>, name: impl Into<String>) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Node {
+            name: name.into(),
+            transform: Transform::default(),
+            parent,
+            children: Vec::new(),
+            mesh: None,
+            world_mat: Matrix4::identity(),
+            dirty: true,
+        });
+        if let Some(parent) = parent {
+            self.nodes[parent.0].children.push(id);
+        }
+        id
+    }
+
+    pub fn set_mesh(&mut self, node: NodeId, mesh: MeshId, material: MaterialId) {
+        self.nodes[node.0].mesh = Some((mesh, material));
+    }
+
+    pub fn set_transform(&mut self, node: NodeId, transform: Transform) {
+        let node = &mut self.nodes[node.0];
+        node.transform = transform;
+        node.dirty = true;
+    }
+
+    pub fn set_translation(&mut self, node: NodeId, translation: [f32; 3]) {
+        let node = &mut self.nodes[node.0];
+        node.transform.translation = translation.into();
+        node.dirty = true;
+    }
+
+    pub fn set_rotation(&mut self, node: NodeId, rotation: Quaternion<f32>) {
+        let node = &mut self.nodes[node.0];
+        node.transform.rotation = rotation;
+        node.dirty = true;
+    }
+
+    pub fn set_scale(&mut self, node: NodeId, scale: [f32; 3]) {
+        let node = &mut self.nodes[node.0];
+        node.transform.scale = scale.into();
+        node.dirty = true;
+    }
+
+    pub fn node(&self, node: NodeId) -> &Node {
+        &self.nodes[node.0]
+    }
+
+    /// Nodes in an order where parents precede their children.
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.nodes.iter().enumerate().map(|(i, node)| (NodeId(i), node))
+    }
+
+    pub fn mesh(&self, mesh: MeshId) -> &Mesh {
+        &self.meshes[mesh.0]
+    }
+
+    pub fn meshes(&self) -> &[Mesh] {
+        &self.meshes
+    }
+
+    pub fn material(&self, material: MaterialId) -> &SurfaceMaterial {
+        &self.materials[material.0]
+    }
+
+    pub fn materials(&self) -> &[SurfaceMaterial] {
+        &self.materials
+    }
+
+    /// Recomputes the world matrices of changed nodes and their
+    /// descendants, and returns how many were recomputed.
+    pub fn update_world_matrices(&mut self) -> usize {
+        let mut changed = vec![false; self.nodes.len()];
+        for i in 0..self.nodes.len() {
+            let parent = self.nodes[i].parent.map(|p| p.0);
+            let parent_changed = parent.is_some_and(|p| changed[p]);
+            if !self.nodes[i].dirty && !parent_changed {
+                continue;
+            }
+            let parent_mat = parent.map_or(Matrix4::identity(), |p| self.nodes[p].world_mat);
+            let node = &mut self.nodes[i];
+            node.world_mat = parent_mat * node.local_matrix();
+            node.dirty = false;
+            changed[i] = true;
+        }
+        changed.iter().filter(|&&c| c).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{Rotation3, Vector4, assert_relative_eq};
+
+    fn position(graph: &SceneGraph, node: NodeId) -> [f32; 3] {
+        let p = graph.node(node).world_matrix() * Vector4::new(0.0, 0.0, 0.0, 1.0);
+        [p.x, p.y, p.z]
+    }
+
+    #[test]
+    fn world_matrices_follow_parents() {
+        let mut graph = SceneGraph::new();
+        let sun = graph.add_node(None, "sun");
+        let planet = graph.add_node(Some(sun), "planet");
+        let moon = graph.add_node(Some(planet), "moon");
+        let comet = graph.add_node(None, "comet");
+        graph.set_translation(planet, [2.0, 0.0, 0.0]);
+        graph.set_translation(moon, [0.5, 0.0, 0.0]);
+        graph.set_scale(sun, [2.0, 2.0, 2.0]);
+        assert_eq!(graph.update_world_matrices(), 4);
+        assert_relative_eq!(position(&graph, moon)[0], 5.0);
+        assert_eq!(graph.node(sun).children(), [planet]);
+        assert_eq!(graph.node(moon).parent(), Some(planet));
+
+        // nothing changed
+        assert_eq!(graph.update_world_matrices(), 0);
+
+        // a quarter turn of the sun carries the planet and moon along,
+        // but not the unrelated comet
+        graph.set_rotation(sun, Quaternion::from_angle_y(cgmath::Deg(90.0)));
+        assert_eq!(graph.update_world_matrices(), 3);
+        let [x, y, z] = position(&graph, moon);
+        assert_relative_eq!(x, 0.0, epsilon = 1e-5);
+        assert_relative_eq!(y, 0.0, epsilon = 1e-5);
+        assert_relative_eq!(z, -5.0, epsilon = 1e-5);
+        assert_eq!(position(&graph, comet), [0.0; 3]);
+
+        // only the moon moved
+        graph.set_translation(moon, [1.0, 0.0, 0.0]);
+        assert_eq!(graph.update_world_matrices(), 1);
+        assert_relative_eq!(position(&graph, moon)[2], -6.0, epsilon = 1e-5);
+    }
+}
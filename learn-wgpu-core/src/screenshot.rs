@@ -0,0 +1,193 @@
+//! Saving rendered frames as PNG files, either on a key press or through the
+//! `--screenshot <path> [--frames <n>]` command line flags.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::readback;
+
+#[derive(Debug)]
+pub enum ScreenshotError {
+    /// The texture was not created with `TextureUsages::COPY_SRC`.
+    NotCopyable,
+    UnsupportedFormat(wgpu::TextureFormat),
+    Image(image::ImageError),
+    /// `--screenshot` or `--frames` is missing its value or has an invalid
+    /// one.
+    Args(String),
+}
+
+impl fmt::Display for ScreenshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreenshotError::NotCopyable =>
+                write!(f, "the frame texture does not allow copies"),
+            ScreenshotError::UnsupportedFormat(format) =>
+                write!(f, "unsupported frame format {:?}", format),
+            ScreenshotError::Image(e) => write!(f, "{}", e),
+            ScreenshotError::Args(message) =>
+                write!(f, "{}\nusage: --screenshot <path> [--frames <n>]", message),
+        }
+    }
+}
+
+impl std::error::Error for ScreenshotError {}
+
+/// Screenshot requested on the command line.
+#[derive(Debug)]
+pub struct ScreenshotArgs {
+    pub path: PathBuf,
+    /// Frame to capture, counting from 1; the program exits afterwards.
+    pub frames: u32,
+}
+
+impl ScreenshotArgs {
+    /// Parses `--screenshot <path>` and the optional `--frames <n>` from the
+    /// process arguments. Returns `None` if no screenshot was requested.
+    pub fn from_env() -> Result<Option<Self>, ScreenshotError> {
+        Self::from_args(std::env::args().skip(1))
+    }
+
+    /// Same as [`ScreenshotArgs::from_env`], for the arguments after the
+    /// program name.
+    pub fn from_args(
+        args: impl IntoIterator<Item = String>
+    ) -> Result<Option<Self>, ScreenshotError> {
+        let mut path = None;
+        let mut frames = 1;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--screenshot" => {
+                    let value = args.next().ok_or_else(|| {
+                        ScreenshotError::Args("--screenshot expects a file path".to_string())
+                    })?;
+                    path = Some(PathBuf::from(value));
+                },
+                "--frames" => {
+                    frames = args
+                        .next()
+                        .and_then(|n| n.parse().ok())
+                        .filter(|n| *n > 0)
+                        .ok_or_else(|| {
+                            ScreenshotError::Args("--frames expects a positive number".to_string())
+                        })?;
+                },
+                _ => {}
+            }
+        }
+        Ok(path.map(|path| Self { path, frames }))
+    }
+}
+
+/// A screenshot of the next rendered frame, if one was requested.
+#[derive(Debug, Default)]
+pub struct Pending {
+    path: Option<PathBuf>,
+}
+
+impl Pending {
+    /// Saves the next rendered frame to `path` as a PNG.
+    pub fn request(&mut self, path: PathBuf) {
+        self.path = Some(path);
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// Saves `texture` if a screenshot was requested, after the frame has
+    /// been submitted and before it is presented. Errors are printed.
+    pub fn save_requested(
+        &mut self, device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture,
+        config: &wgpu::SurfaceConfiguration
+    ) {
+        let Some(path) = self.path.take() else { return };
+        match save(device, queue, texture, config, &path) {
+            Ok(()) => println!("Saved screenshot to {}", path.display()),
+            Err(e) => eprintln!("Failed to save screenshot: {}", e),
+        }
+    }
+}
+
+/// File name used for screenshots taken with the hotkey, in milliseconds so
+/// that two presses within a second don't overwrite each other.
+pub fn timestamped_path() -> PathBuf {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    PathBuf::from(format!("screenshot-{}.png", millis))
+}
+
+/// Reads `texture` back and saves it as an RGBA PNG.
+///
+/// BGRA frames are swizzled to RGBA. sRGB and linear 8-bit formats are
+/// saved as stored, which is what the display shows. When the surface is
+/// composited opaquely the alpha channel is forced to 255.
+pub fn save(
+    device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture,
+    config: &wgpu::SurfaceConfiguration, path: &Path
+) -> Result<(), ScreenshotError> {
+    if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+        return Err(ScreenshotError::NotCopyable);
+    }
+    let swap_red_blue = match config.format {
+        wgpu::TextureFormat::Rgba8Unorm
+        | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+        wgpu::TextureFormat::Bgra8Unorm
+        | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+        format => return Err(ScreenshotError::UnsupportedFormat(format)),
+    };
+    let opaque = matches!(
+        config.alpha_mode,
+        wgpu::CompositeAlphaMode::Opaque | wgpu::CompositeAlphaMode::Auto
+    );
+
+    let mut pixels = readback::read_texture(device, queue, texture);
+    for pixel in pixels.chunks_exact_mut(4) {
+        if swap_red_blue {
+            pixel.swap(0, 2);
+        }
+        if opaque {
+            pixel[3] = 255;
+        }
+    }
+
+    image::save_buffer(
+        path, &pixels, texture.width(), texture.height(),
+        image::ExtendedColorType::Rgba8
+    ).map_err(ScreenshotError::Image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<ScreenshotArgs>, ScreenshotError> {
+        ScreenshotArgs::from_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_screenshot_flags() {
+        assert!(parse(&["--hot-reload"]).unwrap().is_none());
+        let args = parse(&["--screenshot", "out.png", "--frames", "3"]).unwrap().unwrap();
+        assert_eq!(args.path, PathBuf::from("out.png"));
+        assert_eq!(args.frames, 3);
+
+        for args in [&["--screenshot"][..], &["--frames", "0"], &["--frames", "x"]] {
+            let error = parse(args).unwrap_err();
+            assert!(matches!(error, ScreenshotError::Args(_)), "{:?}", args);
+            assert!(error.to_string().contains("usage:"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn pending_until_requested() {
+        let mut pending = Pending::default();
+        assert!(!pending.is_pending());
+        pending.request(PathBuf::from("out.png"));
+        assert!(pending.is_pending());
+    }
+}
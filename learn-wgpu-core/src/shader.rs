@@ -0,0 +1,163 @@
+//! WGSL validation with naga and shader hot-reload for development.
+//!
+//! Hot-reload is opt-in with `--hot-reload` or `WGPU_SHADER_RELOAD=1`: the
+//! renderer then loads its shader and `common.wgsl` from the source tree
+//! instead of the copies baked into the binary, and rebuilds its lit
+//! pipelines whenever either file is saved.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub const HOT_RELOAD_FLAG: &str = "--hot-reload";
+
+/// Vertex stage, lights and shadows of the lit shaders. `shader.wgsl` and
+/// `pbr.wgsl` only compile with this in front of them.
+pub const COMMON_WGSL: &str = include_str!("common.wgsl");
+
+pub fn hot_reload_enabled() -> bool {
+    std::env::var("WGPU_SHADER_RELOAD").is_ok_and(|v| v == "1")
+        || std::env::args().skip(1).any(|arg| arg == HOT_RELOAD_FLAG)
+}
+
+/// Parses and validates WGSL, returning the diagnostic with source spans,
+/// labelled with `path`, on failure.
+pub fn validate_wgsl(
+    source: &str, path: &str
+) -> Result<(naga::Module, naga::valid::ModuleInfo), String> {
+    let module = naga::front::wgsl::parse_str(source)
+        .map_err(|e| e.emit_to_string_with_path(source, path))?;
+    let info = naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(), naga::valid::Capabilities::default()
+    )
+        .validate(&module)
+        .map_err(|e| e.emit_to_string_with_path(source, path))?;
+    Ok((module, info))
+}
+
+/// Watches a shader file by its modification time, which is cheap enough
+/// to check every frame.
+pub struct ShaderWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl ShaderWatcher {
+    /// The first [`ShaderWatcher::poll`] returns the current contents.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), modified: None }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The new source if the file changed since the last call.
+    pub fn poll(&mut self) -> Option<io::Result<String>> {
+        let modified = match std::fs::metadata(&self.path).and_then(|m| m.modified()) {
+            Ok(modified) => modified,
+            // report a missing file once, not every frame
+            Err(_) if self.modified == Some(SystemTime::UNIX_EPOCH) => return None,
+            Err(e) => {
+                self.modified = Some(SystemTime::UNIX_EPOCH);
+                return Some(Err(e));
+            },
+        };
+        if self.modified == Some(modified) {
+            return None;
+        }
+        self.modified = Some(modified);
+        Some(std::fs::read_to_string(&self.path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const LIT_WGSL: &str = concat!(include_str!("common.wgsl"), include_str!("shader.wgsl"));
+
+    #[test]
+    fn reports_errors_with_spans() {
+        let source = "@fragment\nfn fs_main() -> @location(0) vec4<f32> {\n    return vec3<f32>(1.0);\n}\n";
+        let message = validate_wgsl(source, "broken.wgsl").unwrap_err();
+        assert!(message.contains("broken.wgsl:3:"), "{}", message);
+
+        let (module, _) = validate_wgsl(LIT_WGSL, "shader.wgsl").unwrap();
+        let entry_points: Vec<_> = module.entry_points.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(entry_points, ["vs_main", "fs_main"]);
+    }
+
+    #[test]
+    fn failed_reload_keeps_pipeline() {
+        use crate::{common, mesh, vertex_data};
+
+        let (positions, normals, indices) = vertex_data::cube_indexed();
+        let cube = mesh::IndexedMesh::new(&positions, &normals, indices);
+        let material = common::material([1.0, 0.0, 0.0], [1.0, 1.0, 1.0], 0.2, 0.8, 0.4, 30.0);
+        let mut state = pollster::block_on(common::State::new_offscreen_scene(
+            32, 32, &[common::SceneObject::indexed(&cube, material)]
+        ));
+        state.update(Duration::from_secs(1));
+        let before = state.render_to_rgba().unwrap();
+
+        let source = include_str!("shader.wgsl");
+        let broken = source.replace("let N : vec3<f32>", "let N : vec4<f32>");
+        let error = state.reload_shader(COMMON_WGSL, &broken, "shader.wgsl").unwrap_err();
+        assert!(error.contains("shader.wgsl:"), "{}", error);
+        // valid WGSL, but the binding is missing from the pipeline layout
+        let unbound = COMMON_WGSL.replace("@binding(1) @group(0)", "@binding(2) @group(0)");
+        assert!(state.reload_shader(&unbound, source, "shader.wgsl").is_err());
+        assert_eq!(state.render_to_rgba().unwrap(), before);
+
+        let green = source.replace(
+            "return vec4<f32>(color, diffuse_map.a);",
+            "return vec4<f32>(0.0, 1.0, 0.0, 1.0);"
+        );
+        state.reload_shader(COMMON_WGSL, &green, "shader.wgsl").unwrap();
+        assert_ne!(state.render_to_rgba().unwrap(), before);
+    }
+
+    #[test]
+    fn common_source_reloads_the_pbr_pipeline() {
+        use crate::{common, material, mesh, vertex_data};
+
+        let (positions, normals, indices) = vertex_data::cube_indexed();
+        let cube = mesh::IndexedMesh::new(&positions, &normals, indices);
+        let material = material::PbrMaterial::new([1.0, 0.0, 0.0], 0.0, 0.5);
+        let mut state = pollster::block_on(common::State::new_offscreen_scene(
+            32, 32, &[common::SceneObject::indexed(&cube, material)]
+        ));
+        state.update(Duration::from_secs(1));
+        let before = state.render_to_rgba().unwrap();
+
+        // no light reaches the surface
+        let dark = COMMON_WGSL.replace(
+            "let radiance : vec3<f32> = light.color.rgb * light.intensity;",
+            "let radiance : vec3<f32> = vec3<f32>(0.0);"
+        );
+        assert_ne!(dark, COMMON_WGSL);
+        state.reload_shader(&dark, include_str!("shader.wgsl"), "shader.wgsl").unwrap();
+        assert_ne!(state.render_to_rgba().unwrap(), before);
+    }
+
+    #[test]
+    fn watcher_returns_changed_source() {
+        let path = std::env::temp_dir().join(format!("watch-{}.wgsl", std::process::id()));
+        std::fs::write(&path, "// first").unwrap();
+        let mut watcher = ShaderWatcher::new(&path);
+        assert_eq!(watcher.poll().unwrap().unwrap(), "// first");
+        assert!(watcher.poll().is_none());
+
+        std::fs::write(&path, "// second").unwrap();
+        // timestamps can be coarse, so make sure this write is newer
+        let file = std::fs::File::options().append(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(1)).unwrap();
+        assert_eq!(watcher.poll().unwrap().unwrap(), "// second");
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(watcher.poll().unwrap().is_err());
+        assert!(watcher.poll().is_none());
+    }
+}
@@ -0,0 +1,42 @@
+// Blinn-Phong material, appended to common.wgsl
+
+struct Material {
+    ambient : vec4<f32>,
+    diffuse : vec4<f32>,
+    specular : vec4<f32>,
+    shininess : f32,
+};
+
+@binding(0) @group(1) var<uniform> material : Material;
+// white when the material has no texture
+@binding(1) @group(1) var diffuse_texture : texture_2d<f32>;
+@binding(2) @group(1) var specular_texture : texture_2d<f32>;
+@binding(3) @group(1) var material_sampler : sampler;
+
+@fragment
+fn fs_main(
+    @location(0) v_position : vec4<f32>,
+    @location(1) v_normal : vec4<f32>,
+    @location(2) v_uv : vec2<f32>,
+    @location(3) v_color : vec4<f32>
+) -> @location(0) vec4<f32> {
+    let N : vec3<f32> = normalize(v_normal.xyz);
+    let V : vec3<f32> =
+        normalize(frag_uniforms.eye_position.xyz - v_position.xyz);
+    var diffuse : vec3<f32> = vec3<f32>(0.0);
+    var specular : vec3<f32> = vec3<f32>(0.0);
+    for (var i : u32 = 0u; i < min(frag_uniforms.light_count, MAX_LIGHTS); i++) {
+        let light : Incident = incident(frag_uniforms.lights[i], v_position.xyz);
+        let H : vec3<f32> = normalize(light.L + V);
+        diffuse += light.radiance * max(dot(N, light.L), 0.0);
+        specular += light.radiance * pow(max(dot(N, H), 0.0), material.shininess);
+    }
+    let diffuse_map : vec4<f32> =
+        v_color * textureSample(diffuse_texture, material_sampler, v_uv);
+    let specular_map : vec3<f32> =
+        textureSample(specular_texture, material_sampler, v_uv).rgb;
+    let color : vec3<f32> =
+        diffuse_map.rgb * (material.ambient.rgb + material.diffuse.rgb * diffuse) +
+        specular_map * material.specular.rgb * specular;
+    return vec4<f32>(color, diffuse_map.a);
+}
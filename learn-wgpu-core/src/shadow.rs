@@ -0,0 +1,401 @@
+//! Shadow maps for directional and spot lights.
+//!
+//! Each shadow-casting light renders the scene's depth from its own point
+//! of view into one layer of a depth texture array: directional lights
+//! with an orthographic projection around the scene, spot lights with a
+//! perspective projection covering their cone. The lit shaders compare
+//! against it at group 2 with percentage-closer filtering.
+
+use cgmath::{InnerSpace, Matrix4, Point3, Rad, Vector3};
+use wgpu::util::DeviceExt;
+
+use crate::common::TexturedVertex;
+use crate::instance::InstanceRaw;
+use crate::lights::LightSource;
+use crate::{transforms, uniforms};
+
+/// Number of layers of the shadow map; further lights cast no shadows.
+pub const MAX_SHADOWS: usize = 4;
+pub const SHADOW_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ShadowConfig {
+    /// Width and height of each shadow map layer in texels.
+    pub resolution: u32,
+    /// Center and radius of a sphere holding every shadow caster and
+    /// receiver; directional shadows cover exactly this sphere.
+    pub center: [f32; 3],
+    pub radius: f32,
+    /// Constant depth bias in units of the depth format's precision, which
+    /// keeps surfaces from shadowing themselves ("shadow acne").
+    pub depth_bias: i32,
+    /// Depth bias proportional to the surface slope as seen by the light.
+    pub slope_bias: f32,
+    /// Shadow map texels sampled in each direction around the lookup;
+    /// 0 compares a single (hardware-filtered) sample, 1 averages 3x3.
+    pub pcf_radius: u32,
+}
+
+impl Default for ShadowConfig {
+    fn default() -> Self {
+        Self {
+            resolution: 2048,
+            center: [0.0, 0.0, 0.0],
+            radius: 4.0,
+            depth_bias: 2,
+            slope_bias: 2.0,
+            pcf_radius: 1,
+        }
+    }
+}
+
+impl ShadowConfig {
+    /// The light's view-projection matrix, or `None` for point lights,
+    /// which would need a cube map.
+    pub fn light_view_projection(&self, light: &LightSource) -> Option<Matrix4<f32>> {
+        let center = Point3::from(self.center);
+        match light {
+            LightSource::Directional { direction, .. } => {
+                let direction = Vector3::from(*direction).normalize();
+                let r = self.radius;
+                // far enough back that the whole sphere is in front
+                let eye = center - direction * 2.0 * r;
+                let (_, _, view_project_mat) = transforms::create_view_projection_ortho(
+                    -r, r, -r, r, r, 3.0 * r,
+                    eye, center, transforms::up_vector(direction)
+                );
+                Some(view_project_mat)
+            },
+            LightSource::Spot { position, direction, outer_angle, .. } => {
+                let eye = Point3::from(*position);
+                let direction = Vector3::from(*direction).normalize();
+                let far = (center - eye).magnitude() + self.radius;
+                let view_mat = transforms::create_view(
+                    eye, eye + direction, transforms::up_vector(direction)
+                );
+                let fovy = Rad::from(*outer_angle) * 2.0;
+                let project_mat = transforms::create_perspective_projection(
+                    fovy, 1.0, 0.01 * far, far
+                );
+                Some(project_mat * view_mat)
+            },
+            LightSource::Point { .. } => None,
+        }
+    }
+}
+
+/// GPU resources of the shadow maps. Without a config the map is a single
+/// texel per layer and no shadow passes are recorded.
+pub struct ShadowMap {
+    config: Option<ShadowConfig>,
+    layer_views: Vec<wgpu::TextureView>,
+    pipeline: wgpu::RenderPipeline,
+    // light view-projection matrix of each layer, for the shadow passes
+    pass_buffers: Vec<wgpu::Buffer>,
+    pass_bind_groups: Vec<wgpu::BindGroup>,
+    uniforms: uniforms::ShadowUniforms,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    // layers rendered this frame
+    active_layers: usize,
+}
+
+impl ShadowMap {
+    /// `object_layout` is the layout of each object's group 0, whose model
+    /// matrix the shadow pass reads.
+    pub fn new(
+        device: &wgpu::Device, object_layout: &wgpu::BindGroupLayout,
+        sampling_layout: &wgpu::BindGroupLayout, config: Option<ShadowConfig>
+    ) -> Self {
+        let resolution = config.map_or(1, |c| c.resolution);
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow Map"),
+            size: wgpu::Extent3d {
+                width: resolution,
+                height: resolution,
+                depth_or_array_layers: MAX_SHADOWS as u32,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: SHADOW_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let array_view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        let layer_views = (0..MAX_SHADOWS as u32)
+            .map(|layer| texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some("Shadow Map Layer"),
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                base_array_layer: layer,
+                array_layer_count: Some(1),
+                ..Default::default()
+            }))
+            .collect();
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+
+        let uniforms = uniforms::ShadowUniforms::new(config.map_or(0, |c| c.pcf_radius));
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Shadow Uniform Buffer"),
+            contents: bytemuck::bytes_of(&uniforms),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: sampling_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&array_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some("Shadow Bind Group"),
+        });
+
+        let pass_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("Shadow Pass Bind Group Layout"),
+        });
+        let pass_buffers: Vec<_> = (0..MAX_SHADOWS)
+            .map(|_| device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Shadow Pass Uniform Buffer"),
+                size: std::mem::size_of::<[[f32; 4]; 4]>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }))
+            .collect();
+        let pass_bind_groups = pass_buffers
+            .iter()
+            .map(|buffer| device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &pass_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+                label: Some("Shadow Pass Bind Group"),
+            }))
+            .collect();
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shadow Pipeline Layout"),
+            bind_group_layouts: &[object_layout, &pass_layout],
+            immediate_size: 0,
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shadow Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shadow.wgsl").into()),
+        });
+        let default_config = ShadowConfig::default();
+        let config_or_default = config.unwrap_or(default_config);
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shadow Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[TexturedVertex::desc(), InstanceRaw::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: None,
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: SHADOW_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState {
+                    constant: config_or_default.depth_bias,
+                    slope_scale: config_or_default.slope_bias,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            cache: None,
+            multiview_mask: None,
+        });
+
+        Self {
+            config,
+            layer_views,
+            pipeline,
+            pass_buffers,
+            pass_bind_groups,
+            uniforms,
+            uniform_buffer,
+            bind_group,
+            active_layers: 0,
+        }
+    }
+
+    /// Layout of the shadow map as read by the lit shaders at group 2.
+    pub fn sampling_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        sample_type: wgpu::TextureSampleType::Depth,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                    count: None,
+                },
+            ],
+            label: Some("Shadow Bind Group Layout"),
+        })
+    }
+
+    pub fn config(&self) -> Option<&ShadowConfig> {
+        self.config.as_ref()
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    /// Assigns shadow map layers to the first [`MAX_SHADOWS`] directional
+    /// and spot lights and uploads their matrices. `light_uniforms` are the
+    /// uniforms of `lights`, whose `shadow_layer` is updated.
+    pub fn update(
+        &mut self, queue: &wgpu::Queue, lights: &[LightSource],
+        light_uniforms: &mut [uniforms::LightSourceUniforms]
+    ) {
+        self.active_layers = 0;
+        let Some(config) = self.config else { return };
+        for (light, light_uniforms) in lights.iter().zip(light_uniforms) {
+            if self.active_layers == MAX_SHADOWS {
+                break;
+            }
+            let Some(view_project_mat) = config.light_view_projection(light) else { continue };
+            let layer = self.active_layers;
+            let matrix: [[f32; 4]; 4] = view_project_mat.into();
+            self.uniforms.view_project[layer] = matrix;
+            queue.write_buffer(&self.pass_buffers[layer], 0, bytemuck::bytes_of(&matrix));
+            light_uniforms.shadow_layer = layer as i32;
+            self.active_layers += 1;
+        }
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&self.uniforms));
+    }
+
+    /// Records a depth pass for every layer assigned by the last
+    /// [`ShadowMap::update`]; `draw` draws the scene with the bound pipeline
+    /// at group 0 left to it.
+    pub fn render(
+        &self, encoder: &mut wgpu::CommandEncoder, mut draw: impl FnMut(&mut wgpu::RenderPass)
+    ) {
+        for layer in 0..self.active_layers {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Shadow Pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.layer_views[layer],
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+                multiview_mask: None,
+            });
+            rpass.set_pipeline(&self.pipeline);
+            rpass.set_bind_group(1, &self.pass_bind_groups[layer], &[]);
+            draw(&mut rpass);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{Deg, Vector4};
+
+    fn project(mat: Matrix4<f32>, p: [f32; 3]) -> Vector4<f32> {
+        let clip = mat * Vector4::new(p[0], p[1], p[2], 1.0);
+        clip / clip.w
+    }
+
+    fn inside(ndc: Vector4<f32>) -> bool {
+        ndc.x.abs() <= 1.0 && ndc.y.abs() <= 1.0 && (0.0..=1.0).contains(&ndc.z)
+    }
+
+    #[test]
+    fn directional_shadow_covers_scene_sphere() {
+        let config = ShadowConfig { center: [1.0, 0.0, 0.0], radius: 2.0, ..Default::default() };
+        let sun = LightSource::directional([0.0, -1.0, 0.0], [1.0; 3]);
+        let mat = config.light_view_projection(&sun).unwrap();
+        let center = project(mat, [1.0, 0.0, 0.0]);
+        assert!(center.x.abs() < 1e-5 && center.y.abs() < 1e-5, "{:?}", center);
+        for p in [[3.0, 0.0, 0.0], [-1.0, 0.0, 0.0], [1.0, 2.0, 0.0], [1.0, -2.0, 0.0], [1.0, 0.0, 1.99]] {
+            assert!(inside(project(mat, p)), "{:?}", p);
+        }
+        // nearer to the light means smaller depth
+        assert!(project(mat, [1.0, 1.0, 0.0]).z < project(mat, [1.0, -1.0, 0.0]).z);
+    }
+
+    #[test]
+    fn spot_shadow_follows_cone() {
+        let config = ShadowConfig::default();
+        let spot = LightSource::spot(
+            [0.0, 4.0, 0.0], [0.0, -1.0, 0.0], [1.0; 3], Deg(20.0), Deg(30.0)
+        );
+        let mat = config.light_view_projection(&spot).unwrap();
+        let axis = project(mat, [0.0, 0.0, 0.0]);
+        assert!(axis.x.abs() < 1e-5 && axis.y.abs() < 1e-5 && inside(axis), "{:?}", axis);
+        // just inside and outside the 30 degree cone at the floor
+        let edge = 4.0 * 30.0_f32.to_radians().tan();
+        assert!(inside(project(mat, [0.95 * edge, 0.0, 0.0])));
+        assert!(!inside(project(mat, [1.05 * edge, 0.0, 0.0])));
+
+        assert!(config.light_view_projection(&LightSource::point([0.0; 3], [1.0; 3])).is_none());
+    }
+}
@@ -0,0 +1,22 @@
+// depth-only pass from a light's point of view
+
+struct Uniforms {
+    model_mat : mat4x4<f32>,
+    view_project_mat : mat4x4<f32>,
+    normal_mat : mat4x4<f32>,
+};
+
+@binding(0) @group(0) var<uniform> uniforms : Uniforms;
+@binding(0) @group(1) var<uniform> light_view_project : mat4x4<f32>;
+
+@vertex
+fn vs_main(
+    @location(0) pos : vec4<f32>,
+    @location(3) model_mat_0 : vec4<f32>,
+    @location(4) model_mat_1 : vec4<f32>,
+    @location(5) model_mat_2 : vec4<f32>,
+    @location(6) model_mat_3 : vec4<f32>
+) -> @builtin(position) vec4<f32> {
+    let model_mat = mat4x4<f32>(model_mat_0, model_mat_1, model_mat_2, model_mat_3);
+    return light_view_project * uniforms.model_mat * model_mat * pos;
+}
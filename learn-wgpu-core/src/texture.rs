@@ -0,0 +1,80 @@
+//! 2D textures with a sampler, bound through the [`crate::material`] bind
+//! groups.
+
+use std::path::Path;
+
+use wgpu::util::DeviceExt;
+
+pub struct Texture {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+}
+
+impl Texture {
+    /// Uploads an sRGB image. U wraps around so longitudes continue across
+    /// the seam of a sphere; V is clamped so the poles do not bleed.
+    pub fn from_image(
+        device: &wgpu::Device, queue: &wgpu::Queue, image: &image::RgbaImage,
+        label: Option<&str>
+    ) -> Self {
+        Self::with_format(device, queue, image, wgpu::TextureFormat::Rgba8UnormSrgb, label)
+    }
+
+    /// Uploads an image holding data rather than colors, such as a
+    /// metallic-roughness map, without sRGB decoding.
+    pub fn from_linear_image(
+        device: &wgpu::Device, queue: &wgpu::Queue, image: &image::RgbaImage,
+        label: Option<&str>
+    ) -> Self {
+        Self::with_format(device, queue, image, wgpu::TextureFormat::Rgba8Unorm, label)
+    }
+
+    fn with_format(
+        device: &wgpu::Device, queue: &wgpu::Queue, image: &image::RgbaImage,
+        format: wgpu::TextureFormat, label: Option<&str>
+    ) -> Self {
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label,
+                size: wgpu::Extent3d {
+                    width: image.width(),
+                    height: image.height(),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            image.as_raw(),
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label,
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        Self { texture, view, sampler }
+    }
+
+    /// 1x1 white texture, used for objects without one so textured and
+    /// untextured objects share a pipeline.
+    pub fn white(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let image = image::RgbaImage::from_pixel(1, 1, image::Rgba([255, 255, 255, 255]));
+        Self::from_image(device, queue, &image, Some("White Texture"))
+    }
+}
+
+/// Reads an image file into RGBA8 pixels for [`Texture::from_image`].
+pub fn load_image(path: impl AsRef<Path>) -> Result<image::RgbaImage, image::ImageError> {
+    Ok(image::open(path)?.to_rgba8())
+}
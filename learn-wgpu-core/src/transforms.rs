@@ -0,0 +1,499 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use winit::window::Window;
+use cgmath::*;
+
+use crate::config::{InitError, WgpuConfig};
+
+pub const OPENGL_TO_WGPU_MATRIX: Matrix4<f32> = Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
+);
+
+/// Maps clip-space depth `z` to `w - z`, so depth runs from 1 at the near
+/// plane to 0 at the far plane.
+pub const REVERSED_Z_MATRIX: Matrix4<f32> = Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, -1.0, 0.0,
+    0.0, 0.0, 1.0, 1.0,
+);
+
+/// Color format of the offscreen render target.
+pub const OFFSCREEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+pub struct InitWgpu<'a> {
+    pub instance: wgpu::Instance,
+    pub surface: Option<wgpu::Surface<'a>>,
+    pub adapter: wgpu::Adapter,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub config: wgpu::SurfaceConfiguration,
+    pub size: winit::dpi::PhysicalSize<u32>,
+    /// Texture rendered into when there is no surface (offscreen mode).
+    pub target: Option<wgpu::Texture>,
+}
+
+/// A color target acquired for one frame, either from the surface or the
+/// offscreen texture.
+pub enum Frame {
+    Surface(wgpu::SurfaceTexture),
+    Offscreen(wgpu::Texture),
+}
+
+impl Frame {
+    pub fn texture(&self) -> &wgpu::Texture {
+        match self {
+            Frame::Surface(frame) => &frame.texture,
+            Frame::Offscreen(texture) => texture,
+        }
+    }
+
+    pub fn present(self) {
+        if let Frame::Surface(frame) = self {
+            frame.present();
+        }
+    }
+}
+
+impl InitWgpu<'_> {
+    /// Initializes wgpu for `window` with [`WgpuConfig::from_env`].
+    pub async fn init_wgpu(window: Arc<Window>) -> Result<Self, InitError> {
+        let config = WgpuConfig::from_env()?;
+        Self::init_wgpu_with(window, &config).await
+    }
+
+    pub async fn init_wgpu_with(
+        window: Arc<Window>, wgpu_config: &WgpuConfig
+    ) -> Result<Self, InitError> {
+        let size = window.inner_size();
+        let instance = wgpu_config.create_instance();
+        let surface = instance
+            .create_surface(window)
+            .map_err(InitError::Surface)?;
+        let adapter = wgpu_config.request_adapter(&instance, Some(&surface)).await?;
+        let (device, queue) = wgpu_config.request_device(&adapter).await?;
+
+        let surface_caps = surface.get_capabilities(&adapter);
+        let config = wgpu::SurfaceConfiguration {
+            // copies out of the frame are needed for screenshots
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                 | (surface_caps.usages & wgpu::TextureUsages::COPY_SRC),
+            format: wgpu_config.surface_format(&surface_caps),
+            width: size.width,
+            height: size.height,
+            present_mode: wgpu_config.present_mode(&surface_caps),
+            alpha_mode: surface_caps.alpha_modes[0],
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        surface.configure(&device, &config);
+
+        Ok(Self {
+            instance,
+            surface: Some(surface),
+            adapter,
+            device,
+            queue,
+            config,
+            size,
+            target: None,
+        })
+    }
+
+    /// Initializes wgpu without a window, rendering into a texture of the
+    /// given size. A software (fallback) adapter is preferred so this works
+    /// on machines without a display or GPU; any other adapter is used if
+    /// no fallback adapter is available.
+    pub async fn init_offscreen(width: u32, height: u32) -> Self {
+        let size = winit::dpi::PhysicalSize::new(width, height);
+        // software adapters often fall short of the default limits
+        let mut wgpu_config = WgpuConfig {
+            force_fallback_adapter: true,
+            required_limits: wgpu::Limits::downlevel_defaults(),
+            ..Default::default()
+        };
+        let instance = wgpu_config.create_instance();
+        let adapter = match wgpu_config.request_adapter(&instance, None).await {
+            Ok(adapter) => adapter,
+            Err(_) => {
+                wgpu_config.force_fallback_adapter = false;
+                wgpu_config
+                    .request_adapter(&instance, None)
+                    .await
+                    .unwrap_or_else(|e| panic!("{}", e))
+            },
+        };
+        let (device, queue) = wgpu_config
+            .request_device(&adapter)
+            .await
+            .unwrap_or_else(|e| panic!("{}", e));
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                 | wgpu::TextureUsages::COPY_SRC,
+            format: OFFSCREEN_FORMAT,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        let target = create_target(&device, &config);
+
+        Self {
+            instance,
+            surface: None,
+            adapter,
+            device,
+            queue,
+            config,
+            size,
+            target: Some(target),
+        }
+    }
+
+    /// Reconfigures the surface, or recreates the offscreen target, with
+    /// the new size.
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+        self.instance.poll_all(true);
+        self.size = new_size;
+        self.config.width = new_size.width;
+        self.config.height = new_size.height;
+        match &self.surface {
+            Some(surface) => surface.configure(&self.device, &self.config),
+            None => self.target = Some(create_target(&self.device, &self.config)),
+        }
+    }
+
+    pub fn get_current_frame(&self) -> Result<Frame, wgpu::SurfaceError> {
+        match (&self.surface, &self.target) {
+            (Some(surface), _) => surface.get_current_texture().map(Frame::Surface),
+            (None, Some(texture)) => Ok(Frame::Offscreen(texture.clone())),
+            (None, None) => Err(wgpu::SurfaceError::Lost),
+        }
+    }
+}
+
+fn create_target(
+    device: &wgpu::Device, config: &wgpu::SurfaceConfiguration
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Offscreen Target"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: config.usage,
+        view_formats: &[],
+    })
+}
+
+/// An up vector for looking along `direction`: y, or z when `direction`
+/// is (nearly) vertical, so the two are never parallel.
+pub fn up_vector(direction: Vector3<f32>) -> Vector3<f32> {
+    if direction.y.abs() > 0.99 { Vector3::unit_z() } else { Vector3::unit_y() }
+}
+
+pub fn create_view(
+    camera_position: Point3<f32>, look_direction: Point3<f32>,
+    up_direction: Vector3<f32>
+) -> Matrix4<f32> {
+    Matrix4::look_at_rh(camera_position, look_direction, up_direction)
+}
+
+pub fn create_projection(aspect: f32, is_perspective: bool) -> Matrix4<f32> {
+    if is_perspective {
+        OPENGL_TO_WGPU_MATRIX * perspective(Rad(2.0*PI/5.0), aspect, 0.1, 100.0)
+    } else {
+        OPENGL_TO_WGPU_MATRIX * ortho(-4.0, 4.0, -3.0, 3.0, -1.0, 6.0)
+    }
+}
+
+pub fn create_view_projection(
+    camera_position: Point3<f32>, look_direction: Point3<f32>,
+    up_direction: Vector3<f32>,
+    aspect: f32, is_perspective: bool
+) -> (Matrix4<f32>, Matrix4<f32>, Matrix4<f32>) {
+    let view_mat = Matrix4::look_at_rh(camera_position, look_direction, up_direction);
+
+    let project_mat = create_projection(aspect, is_perspective);
+
+    let view_project_mat = project_mat * view_mat;
+
+    (view_mat, project_mat, view_project_mat)
+}
+
+pub fn create_perspective_projection(
+    fovy: Rad<f32>, aspect: f32, near: f32, far: f32
+) -> Matrix4<f32> {
+    OPENGL_TO_WGPU_MATRIX * perspective(fovy, aspect, near, far)
+}
+
+/// The projection for a reversed-Z depth buffer, to be drawn with a
+/// `GreaterEqual` depth test and cleared to 0.
+pub fn reverse_z(project_mat: Matrix4<f32>) -> Matrix4<f32> {
+    REVERSED_Z_MATRIX * project_mat
+}
+
+pub fn create_projection_ortho(
+    left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32
+) -> Matrix4<f32> {
+    OPENGL_TO_WGPU_MATRIX * ortho(left, right, bottom, top, near, far)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_view_projection_ortho(
+    left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32,
+    camera_position: Point3<f32>, look_direction: Point3<f32>,
+    up_direction: Vector3<f32>,
+) -> (Matrix4<f32>, Matrix4<f32>, Matrix4<f32>) {
+    let view_mat = Matrix4::look_at_rh(camera_position, look_direction, up_direction);
+
+    let project_mat = create_projection_ortho(left, right, bottom, top, near, far);
+
+    let view_project_mat = project_mat * view_mat;
+
+    (view_mat, project_mat, view_project_mat)
+}
+
+/// Model matrix rotating by the Euler angles `rotation` in radians about
+/// x, then y, then z, like [`Transform::from_euler`] with [`EulerOrder::XYZ`].
+pub fn create_transforms(
+    translation: [f32; 3], rotation: [f32; 3], scaling: [f32; 3]
+) -> Matrix4<f32> {
+    let trans_mat = Matrix4::from_translation(Vector3::new(
+        translation[0], translation[1], translation[2]
+    ));
+    let rotate_mat_x = Matrix4::from_angle_x(Rad(rotation[0]));
+    let rotate_mat_y = Matrix4::from_angle_y(Rad(rotation[1]));
+    let rotate_mat_z = Matrix4::from_angle_z(Rad(rotation[2]));
+    let scale_mat = Matrix4::from_nonuniform_scale(
+        scaling[0], scaling[1], scaling[2]
+    );
+
+    // combine all matrices to form a final transform matrix: model matrix
+    trans_mat * rotate_mat_z * rotate_mat_y * rotate_mat_x * scale_mat
+}
+
+/// The order in which Euler angles are applied: `XYZ` rotates about x
+/// first and z last, which is the matrix `Z * Y * X`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EulerOrder {
+    XYZ,
+    XZY,
+    YXZ,
+    YZX,
+    ZXY,
+    ZYX,
+}
+
+impl EulerOrder {
+    // axis indices in the order they are applied
+    fn axes(self) -> [usize; 3] {
+        match self {
+            EulerOrder::XYZ => [0, 1, 2],
+            EulerOrder::XZY => [0, 2, 1],
+            EulerOrder::YXZ => [1, 0, 2],
+            EulerOrder::YZX => [1, 2, 0],
+            EulerOrder::ZXY => [2, 0, 1],
+            EulerOrder::ZYX => [2, 1, 0],
+        }
+    }
+}
+
+/// Translation, rotation and scale, applied to a model in reverse order.
+/// The rotation is a unit quaternion, which, unlike Euler angles, does not
+/// gimbal-lock and interpolates along the shortest arc.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: Vector3::zero(),
+            rotation: Quaternion::one(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl Transform {
+    pub fn new(translation: [f32; 3], rotation: Quaternion<f32>, scale: [f32; 3]) -> Self {
+        Self { translation: translation.into(), rotation, scale: scale.into() }
+    }
+
+    /// A rotation by the Euler angles `angles` in radians about x, y and z,
+    /// applied in `order`.
+    pub fn from_euler(angles: [f32; 3], order: EulerOrder) -> Self {
+        let axes = [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()];
+        let rotation = order.axes().iter().fold(Quaternion::one(), |q, &axis| {
+            Quaternion::from_axis_angle(axes[axis], Rad(angles[axis])) * q
+        });
+        Self { rotation, ..Self::default() }
+    }
+
+    /// A rotation by `angle` counter-clockwise about `axis`, which need not
+    /// be normalized.
+    pub fn from_axis_angle(axis: [f32; 3], angle: impl Into<Rad<f32>>) -> Self {
+        let axis = Vector3::from(axis).normalize();
+        Self { rotation: Quaternion::from_axis_angle(axis, angle), ..Self::default() }
+    }
+
+    /// The rotation turning -z, which cameras look along, toward `forward`
+    /// and keeping y as close to `up` as possible. When `forward` is
+    /// parallel to `up`, another up vector is picked.
+    pub fn look_rotation(forward: [f32; 3], up: [f32; 3]) -> Self {
+        let z = -Vector3::from(forward).normalize();
+        let up = Vector3::from(up).normalize();
+        let up = if up.dot(z).abs() > 0.99 { up_vector(z) } else { up };
+        let x = up.cross(z).normalize();
+        let y = z.cross(x);
+        Self { rotation: Matrix3::from_cols(x, y, z).into(), ..Self::default() }
+    }
+
+    pub fn with_translation(mut self, translation: [f32; 3]) -> Self {
+        self.translation = translation.into();
+        self
+    }
+
+    pub fn with_scale(mut self, scale: [f32; 3]) -> Self {
+        self.scale = scale.into();
+        self
+    }
+
+    /// Interpolates linearly between translations and scales, and along
+    /// the shortest arc between rotations; `t` runs from 0 at `self` to 1
+    /// at `other`.
+    pub fn slerp(&self, other: &Transform, t: f32) -> Self {
+        Self {
+            translation: self.translation.lerp(other.translation, t),
+            rotation: self.rotation.slerp(other.rotation, t),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+
+    pub fn to_matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.translation)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+}
+
+impl From<Transform> for Matrix4<f32> {
+    fn from(transform: Transform) -> Self {
+        transform.to_matrix()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn depth(project_mat: Matrix4<f32>, z: f32) -> f32 {
+        let clip = project_mat * Vector4::new(0.0, 0.0, z, 1.0);
+        clip.z / clip.w
+    }
+
+    #[test]
+    fn reverse_z_swaps_near_and_far() {
+        let project_mat = create_perspective_projection(Rad(1.0), 1.5, 0.1, 100.0);
+        let reversed = reverse_z(project_mat);
+        assert!(depth(project_mat, -0.1).abs() < 1e-5);
+        assert!((depth(reversed, -0.1) - 1.0).abs() < 1e-5);
+        assert!((depth(project_mat, -100.0) - 1.0).abs() < 1e-5);
+        assert!(depth(reversed, -100.0).abs() < 1e-5);
+        for z in [-0.5, -3.0, -40.0] {
+            assert!((depth(reversed, z) - (1.0 - depth(project_mat, z))).abs() < 1e-5);
+        }
+        // x and y are untouched
+        let p = Vector4::new(0.3, -0.2, -5.0, 1.0);
+        let (a, b) = (project_mat * p, reversed * p);
+        assert_eq!((a.x, a.y, a.w), (b.x, b.y, b.w));
+    }
+
+    fn assert_matrix_eq(a: Matrix4<f32>, b: Matrix4<f32>) {
+        let (a, b): ([[f32; 4]; 4], [[f32; 4]; 4]) = (a.into(), b.into());
+        for (a, b) in a.iter().flatten().zip(b.iter().flatten()) {
+            assert!((a - b).abs() < 1e-5, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn euler_transform_matches_create_transforms() {
+        let (translation, scale) = ([1.0, -2.0, 0.5], [2.0, 0.5, 1.5]);
+        for angles in [[0.3, -1.2, 2.0], [PI / 2.0, PI / 2.0, 0.0], [-0.7, 0.0, 3.0]] {
+            let transform = Transform::from_euler(angles, EulerOrder::XYZ)
+                .with_translation(translation)
+                .with_scale(scale);
+            assert_matrix_eq(
+                transform.to_matrix(), create_transforms(translation, angles, scale)
+            );
+        }
+
+        // other orders multiply the axis rotations the other way around
+        let [x, y, z] = [0.3, -1.2, 2.0];
+        let (rx, ry, rz) = (
+            Matrix4::from_angle_x(Rad(x)), Matrix4::from_angle_y(Rad(y)),
+            Matrix4::from_angle_z(Rad(z))
+        );
+        for (order, mat) in [
+            (EulerOrder::XZY, ry * rz * rx), (EulerOrder::YXZ, rz * rx * ry),
+            (EulerOrder::YZX, rx * rz * ry), (EulerOrder::ZXY, ry * rx * rz),
+            (EulerOrder::ZYX, rx * ry * rz),
+        ] {
+            assert_matrix_eq(Transform::from_euler([x, y, z], order).into(), mat);
+        }
+    }
+
+    #[test]
+    fn axis_angle_and_look_rotations() {
+        assert_matrix_eq(
+            Transform::from_axis_angle([0.0, 2.0, 0.0], Rad(0.8)).to_matrix(),
+            create_transforms([0.0; 3], [0.0, 0.8, 0.0], [1.0; 3])
+        );
+
+        let forward = Vector3::new(1.0, 0.5, -2.0).normalize();
+        let look = Transform::look_rotation(forward.into(), [0.0, 1.0, 0.0]).to_matrix();
+        let turned = look * Vector4::new(0.0, 0.0, -1.0, 0.0);
+        assert!((turned.truncate() - forward).magnitude() < 1e-5);
+        // the rotated x axis stays horizontal
+        assert!((look * Vector4::unit_x()).y.abs() < 1e-5);
+
+        // looking straight down still turns -z toward forward
+        let down = Transform::look_rotation([0.0, -1.0, 0.0], [0.0, 1.0, 0.0]).to_matrix();
+        let turned = down * Vector4::new(0.0, 0.0, -1.0, 0.0);
+        assert!((turned.truncate() - Vector3::new(0.0, -1.0, 0.0)).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn slerp_follows_the_shortest_arc() {
+        let a = Transform::default();
+        let b = Transform::from_axis_angle([0.0, 1.0, 0.0], Deg(90.0))
+            .with_translation([2.0, 0.0, 0.0])
+            .with_scale([3.0; 3]);
+        let half = a.slerp(&b, 0.5);
+        assert_matrix_eq(
+            half.to_matrix(),
+            create_transforms([1.0, 0.0, 0.0], [0.0, PI / 4.0, 0.0], [2.0; 3])
+        );
+
+        // the same orientation, with the quaternion negated
+        let flipped = Transform { rotation: -b.rotation, ..b };
+        assert_matrix_eq(a.slerp(&flipped, 0.5).to_matrix(), half.to_matrix());
+        assert_matrix_eq(a.slerp(&b, 1.0).to_matrix(), b.to_matrix());
+    }
+}
@@ -0,0 +1,288 @@
+//! Uniform buffer contents of `shader.wgsl` and `pbr.wgsl` as `#[repr(C)]`
+//! structs.
+//!
+//! WGSL lays out uniform structs with `vec4` and `mat4x4` members aligned
+//! to 16 bytes, and rounds the struct size up to a multiple of 16. The
+//! layouts below are checked against those rules at compile time, and a
+//! test reflects the shaders with naga to confirm they match their
+//! declarations.
+
+use std::mem::{offset_of, size_of};
+
+use bytemuck::{Pod, Zeroable};
+use cgmath::{Matrix, Matrix4, SquareMatrix};
+
+use crate::lights::{LightSource, MAX_LIGHTS};
+use crate::shadow::MAX_SHADOWS;
+
+/// `Uniforms` at group 0, binding 0.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct VertexUniforms {
+    pub model_mat: [[f32; 4]; 4],
+    pub view_project_mat: [[f32; 4]; 4],
+    pub normal_mat: [[f32; 4]; 4],
+}
+
+impl VertexUniforms {
+    /// The normal matrix is the inverse transpose of `model_mat`, or the
+    /// identity if it is singular, e.g. scaled to zero.
+    pub fn new(model_mat: Matrix4<f32>, view_project_mat: Matrix4<f32>) -> Self {
+        let normal_mat = model_mat.invert().unwrap_or(Matrix4::identity()).transpose();
+        Self {
+            model_mat: model_mat.into(),
+            view_project_mat: view_project_mat.into(),
+            normal_mat: normal_mat.into(),
+        }
+    }
+}
+
+/// Values of `LightSource::kind` in the shader.
+pub const POINT_LIGHT: u32 = 0;
+pub const DIRECTIONAL_LIGHT: u32 = 1;
+pub const SPOT_LIGHT: u32 = 2;
+/// `LightSource::shadow_layer` of lights that cast no shadow.
+pub const NO_SHADOW: i32 = -1;
+
+/// One element of the light array in [`FragUniforms`], built by
+/// [`LightSource::to_uniforms`].
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct LightSourceUniforms {
+    pub position: [f32; 4],
+    /// Unit vector the light travels along.
+    pub direction: [f32; 4],
+    pub color: [f32; 4],
+    /// Constant, linear and quadratic terms; w is unused.
+    pub attenuation: [f32; 4],
+    pub kind: u32,
+    pub cos_inner: f32,
+    pub cos_outer: f32,
+    pub intensity: f32,
+    /// Layer of the shadow map, assigned by [`crate::shadow::ShadowMap`],
+    /// or [`NO_SHADOW`].
+    pub shadow_layer: i32,
+    pub(crate) _padding: [u32; 3],
+}
+
+/// `FragUniforms` at group 0, binding 1: the eye and the first
+/// `light_count` entries of `lights`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct FragUniforms {
+    pub eye_position: [f32; 4],
+    pub light_count: u32,
+    _padding: [u32; 3],
+    pub lights: [LightSourceUniforms; MAX_LIGHTS],
+}
+
+impl FragUniforms {
+    /// Panics if there are more than [`MAX_LIGHTS`] lights.
+    pub fn new(eye_position: [f32; 3], lights: &[LightSource]) -> Self {
+        let [ex, ey, ez] = eye_position;
+        let mut uniforms = Self {
+            eye_position: [ex, ey, ez, 1.0],
+            ..Self::zeroed()
+        };
+        uniforms.set_lights(lights);
+        uniforms
+    }
+
+    /// Panics if there are more than [`MAX_LIGHTS`] lights.
+    pub fn set_lights(&mut self, lights: &[LightSource]) {
+        assert!(
+            lights.len() <= MAX_LIGHTS,
+            "{} lights given, the shader supports {}", lights.len(), MAX_LIGHTS
+        );
+        self.light_count = lights.len() as u32;
+        for (uniforms, light) in self.lights.iter_mut().zip(lights) {
+            *uniforms = light.to_uniforms();
+        }
+    }
+}
+
+/// `ShadowUniforms` at group 2, binding 0: the view-projection matrix of
+/// each shadow map layer and the PCF kernel radius in texels.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct ShadowUniforms {
+    pub view_project: [[[f32; 4]; 4]; MAX_SHADOWS],
+    pub pcf_radius: u32,
+    _padding: [u32; 3],
+}
+
+impl ShadowUniforms {
+    pub fn new(pcf_radius: u32) -> Self {
+        Self { pcf_radius, ..Self::zeroed() }
+    }
+}
+
+/// `Material` at group 1, binding 0, built by
+/// [`crate::material::Material::to_uniforms`].
+/// The w components are unused.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct MaterialUniforms {
+    pub ambient: [f32; 4],
+    pub diffuse: [f32; 4],
+    pub specular: [f32; 4],
+    pub shininess: f32,
+    _padding: [f32; 3],
+}
+
+impl MaterialUniforms {
+    pub fn new(
+        ambient: [f32; 3], diffuse: [f32; 3], specular: [f32; 3], shininess: f32
+    ) -> Self {
+        let vec4 = |[r, g, b]: [f32; 3]| [r, g, b, 1.0];
+        Self {
+            ambient: vec4(ambient),
+            diffuse: vec4(diffuse),
+            specular: vec4(specular),
+            shininess,
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+/// `PbrMaterial` at group 1, binding 0 of `pbr.wgsl`, built by
+/// [`crate::material::PbrMaterial::to_uniforms`].
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct PbrMaterialUniforms {
+    pub base_color: [f32; 4],
+    pub metallic: f32,
+    pub roughness: f32,
+    _padding: [f32; 2],
+}
+
+impl PbrMaterialUniforms {
+    pub fn new(base_color: [f32; 4], metallic: f32, roughness: f32) -> Self {
+        Self { base_color, metallic, roughness, _padding: [0.0; 2] }
+    }
+}
+
+/// Fails to compile unless the struct has the given size, which must be a
+/// multiple of 16, and each field sits at the given offset.
+macro_rules! assert_uniform_layout {
+    ($ty:ty, $size:expr, { $($field:ident: $offset:expr),* $(,)? }) => {
+        const _: () = {
+            assert!(size_of::<$ty>() == $size);
+            assert!(size_of::<$ty>() % 16 == 0);
+            $(assert!(offset_of!($ty, $field) == $offset);)*
+        };
+    };
+}
+
+assert_uniform_layout!(VertexUniforms, 192, {
+    model_mat: 0,
+    view_project_mat: 64,
+    normal_mat: 128,
+});
+
+assert_uniform_layout!(LightSourceUniforms, 96, {
+    position: 0,
+    direction: 16,
+    color: 32,
+    attenuation: 48,
+    kind: 64,
+    cos_inner: 68,
+    cos_outer: 72,
+    intensity: 76,
+    shadow_layer: 80,
+});
+
+assert_uniform_layout!(FragUniforms, 32 + 96 * MAX_LIGHTS, {
+    eye_position: 0,
+    light_count: 16,
+    lights: 32,
+});
+
+assert_uniform_layout!(ShadowUniforms, 64 * MAX_SHADOWS + 16, {
+    view_project: 0,
+    pcf_radius: 64 * MAX_SHADOWS,
+});
+
+assert_uniform_layout!(MaterialUniforms, 64, {
+    ambient: 0,
+    diffuse: 16,
+    specular: 32,
+    shininess: 48,
+});
+
+assert_uniform_layout!(PbrMaterialUniforms, 32, {
+    base_color: 0,
+    metallic: 16,
+    roughness: 20,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Size and member offsets of a struct declared in the shader.
+    fn reflect(module: &naga::Module, name: &str) -> (u32, Vec<(String, u32)>) {
+        let (_, ty) = module
+            .types
+            .iter()
+            .find(|(_, ty)| ty.name.as_deref() == Some(name))
+            .unwrap_or_else(|| panic!("shader.wgsl has no struct {}", name));
+        match &ty.inner {
+            naga::TypeInner::Struct { members, span } => (
+                *span,
+                members
+                    .iter()
+                    .map(|m| (m.name.clone().unwrap_or_default(), m.offset))
+                    .collect(),
+            ),
+            inner => panic!("{} is not a struct: {:?}", name, inner),
+        }
+    }
+
+    macro_rules! assert_matches_shader {
+        ($module:expr, $wgsl:expr, $ty:ty, [$($field:ident),*]) => {
+            let (span, members) = reflect($module, $wgsl);
+            let rust: Vec<(String, u32)> = vec![
+                $((stringify!($field).to_string(), offset_of!($ty, $field) as u32)),*
+            ];
+            assert_eq!(members, rust, "members of {}", $wgsl);
+            // uniform buffers round the struct size up to 16 bytes
+            assert_eq!(span.next_multiple_of(16) as usize, size_of::<$ty>(), "size of {}", $wgsl);
+        };
+    }
+
+    #[test]
+    fn structs_match_shader() {
+        let parse = |wgsl: &str| {
+            naga::front::wgsl::parse_str(&[crate::shader::COMMON_WGSL, wgsl].concat())
+                .unwrap_or_else(|e| panic!("{}", e))
+        };
+        let module = parse(include_str!("shader.wgsl"));
+        assert_matches_shader!(
+            &module, "Uniforms", VertexUniforms,
+            [model_mat, view_project_mat, normal_mat]
+        );
+        assert_matches_shader!(
+            &module, "LightSource", LightSourceUniforms,
+            [
+                position, direction, color, attenuation, kind, cos_inner, cos_outer,
+                intensity, shadow_layer
+            ]
+        );
+        assert_matches_shader!(
+            &module, "FragUniforms", FragUniforms, [eye_position, light_count, lights]
+        );
+        assert_matches_shader!(
+            &module, "ShadowUniforms", ShadowUniforms, [view_project, pcf_radius]
+        );
+        assert_matches_shader!(
+            &module, "Material", MaterialUniforms, [ambient, diffuse, specular, shininess]
+        );
+
+        // the vertex and light uniforms come from common.wgsl as well
+        let module = parse(include_str!("pbr.wgsl"));
+        assert_matches_shader!(
+            &module, "PbrMaterial", PbrMaterialUniforms, [base_color, metallic, roughness]
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use crate::parametric;
+
+/// [`sphere_indexed`] as a triangle list with equirectangular UVs: u grows
+/// with the longitude `phi` and v from the north to the south pole.
+///
+/// UVs are computed per triangle, so the triangles along the seam end at
+/// `u = 1` instead of wrapping back to 0, and pole vertices take the u in
+/// the middle of their triangle to avoid twisting the texture into a fan.
+#[allow(clippy::type_complexity)]
+pub fn sphere_data(
+    r: f32, u: usize, v: usize
+) -> (Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<[f32; 2]>) {
+    let sphere = parametric::sphere(r, u, v);
+    let capacity = sphere.indices.len();
+    let mut positions: Vec<[f32; 3]> = Vec::with_capacity(capacity);
+    let mut normals: Vec<[f32; 3]> = Vec::with_capacity(capacity);
+    let mut uvs: Vec<[f32; 2]> = Vec::with_capacity(capacity);
+
+    for triangle in sphere.indices.chunks_exact(3) {
+        // the surface runs over (theta, phi), the texture over (phi, theta)
+        let longitudes = triangle.iter().map(|&i| sphere.uvs[i as usize][1]);
+        let min = longitudes.clone().fold(f32::INFINITY, f32::min);
+        let max = longitudes.fold(f32::NEG_INFINITY, f32::max);
+        for &i in triangle {
+            let [theta, phi] = sphere.uvs[i as usize];
+            let is_pole = theta == 0.0 || theta == 1.0;
+            positions.push(sphere.positions[i as usize]);
+            normals.push(sphere.normals[i as usize]);
+            uvs.push([if is_pole { 0.5 * (min + max) } else { phi }, theta]);
+        }
+    }
+
+    (positions, normals, uvs)
+}
+
+/// [`torus_indexed`] as a triangle list.
+pub fn torus_data(
+    r_torus: f32, r_tube: f32, n_torus: usize, n_tube: usize
+) -> (Vec<[f32; 3]>, Vec<[f32; 3]>) {
+    parametric::torus(r_torus, r_tube, n_torus, n_tube)
+        .mesh()
+        .to_soup()
+        .iter()
+        .map(|v| {
+            let [x, y, z, _] = v.position;
+            let [nx, ny, nz, _] = v.normal;
+            ([x, y, z], [nx, ny, nz])
+        })
+        .unzip()
+}
+
+/// Sphere with shared vertices: a `u` x `v` grid of rings and meridians
+/// (the seam meridian and the pole rows are duplicated) and indices into it.
+#[allow(clippy::type_complexity)]
+pub fn sphere_indexed(
+    r: f32, u: usize, v: usize
+) -> (Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<u32>) {
+    let sphere = parametric::sphere(r, u, v);
+    (sphere.positions, sphere.normals, sphere.indices)
+}
+
+/// Torus with shared vertices and smooth normals on an `n_torus` x
+/// `n_tube` grid.
+#[allow(clippy::type_complexity)]
+pub fn torus_indexed(
+    r_torus: f32, r_tube: f32, n_torus: usize, n_tube: usize
+) -> (Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<u32>) {
+    let torus = parametric::torus(r_torus, r_tube, n_torus, n_tube);
+    (torus.positions, torus.normals, torus.indices)
+}
+
+/// Cube with four vertices per face, so each face keeps its own normal.
+#[allow(clippy::type_complexity)]
+pub fn cube_indexed() -> (Vec<[f32; 3]>, Vec<[f32; 3]>, Vec<u32>) {
+    let soup_positions = cube_positions();
+    let soup_normals = cube_normals();
+    let mut positions: Vec<[f32; 3]> = Vec::with_capacity(24);
+    let mut normals: Vec<[f32; 3]> = Vec::with_capacity(24);
+    let mut indices: Vec<u32> = Vec::with_capacity(36);
+
+    // every face in cube_positions is laid out as a, b, c, c, b, d
+    for face in 0..6 {
+        let base = positions.len() as u32;
+        for k in [0, 1, 2, 5] {
+            let p = soup_positions[6 * face + k];
+            let n = soup_normals[6 * face + k];
+            positions.push(p.map(f32::from));
+            normals.push(n.map(f32::from));
+        }
+        indices.extend([0, 1, 2, 2, 1, 3].map(|i| base + i));
+    }
+
+    (positions, normals, indices)
+}
+
+pub fn cube_positions() -> Vec<[i8; 3]> {
+    [
+        // front (0, 0, 1)
+        [-1, -1,  1], [1, -1,  1], [-1,  1,  1], [-1,  1,  1], [ 1, -1,  1], [ 1,  1,  1],
+
+        // right (1, 0, 0)
+        [ 1, -1,  1], [1, -1, -1], [ 1,  1,  1], [ 1,  1,  1], [ 1, -1, -1], [ 1,  1, -1],
+
+        // back (0, 0, -1)
+        [ 1, -1, -1], [-1, -1, -1], [1,  1, -1], [ 1,  1, -1], [-1, -1, -1], [-1,  1, -1],
+
+        // left (-1, 0, 0)
+        [-1, -1, -1], [-1, -1,  1], [-1,  1, -1], [-1,  1, -1], [-1, -1,  1], [-1,  1,  1],
+
+        // top (0, 1, 0)
+        [-1,  1,  1], [ 1,  1,  1], [-1,  1, -1], [-1,  1, -1], [ 1,  1,  1], [ 1,  1, -1],
+
+        // bottom (0, -1, 0)
+        [-1, -1, -1], [ 1, -1, -1], [-1, -1,  1], [-1, -1,  1], [ 1, -1, -1], [ 1, -1,  1],
+    ].to_vec()
+}
+
+pub fn cube_colors() -> Vec<[i8; 3]> {
+    [
+        // front - blue
+        [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1],
+
+        // right - red
+        [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0],
+
+        // back - yellow
+        [1, 1, 0], [1, 1, 0], [1, 1, 0], [1, 1, 0], [1, 1, 0], [1, 1, 0],
+
+        // left - aqua
+        [0, 1, 1], [0, 1, 1], [0, 1, 1], [0, 1, 1], [0, 1, 1], [0, 1, 1],
+
+        // top - green
+        [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0],
+
+        // bottom - fuchsia
+        [1, 0, 1], [1, 0, 1], [1, 0, 1], [1, 0, 1], [1, 0, 1], [1, 0, 1],
+    ].to_vec()
+}
+
+pub fn cube_normals() -> Vec<[i8; 3]> {
+    [
+        // front
+        [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1], [0, 0, 1],
+
+        // right
+        [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0], [1, 0, 0],
+
+        // back
+        [0, 0, -1], [0, 0, -1], [0, 0, -1], [0, 0, -1], [0, 0, -1], [0, 0, -1],
+
+        // left
+        [-1, 0, 0], [-1, 0, 0], [-1, 0, 0], [-1, 0, 0], [-1, 0, 0], [-1, 0, 0],
+
+        // top
+        [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0], [0, 1, 0],
+
+        // bottom
+        [0, -1, 0], [0, -1, 0], [0, -1, 0], [0, -1, 0], [0, -1, 0], [0, -1, 0],
+    ].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sphere_uvs_cover_the_texture_without_wrapping() {
+        let (positions, _, uvs) = sphere_data(1.0, 5, 9);
+        assert_eq!(uvs.len(), positions.len());
+        assert!(uvs.iter().all(|uv| (0.0..=1.0).contains(&uv[0]) && (0.0..=1.0).contains(&uv[1])));
+
+        // every triangle spans at most one cell, also across the seam
+        for triangle in uvs.chunks_exact(3) {
+            for a in triangle {
+                for b in triangle {
+                    assert!((a[0] - b[0]).abs() <= 1.0 / 8.0 + 1e-6, "{:?}", triangle);
+                }
+            }
+        }
+        assert!(uvs.iter().any(|uv| uv[0] == 1.0));
+    }
+
+    #[test]
+    fn sphere_pole_uvs_use_the_cell_center() {
+        let (positions, _, uvs) = sphere_data(1.0, 5, 9);
+        for (p, uv) in positions.iter().zip(&uvs) {
+            if p[1] == 1.0 {
+                assert_eq!(uv[1], 0.0);
+                // centers of the 8 cells of the first row
+                assert_eq!((uv[0] * 16.0).round() % 2.0, 1.0, "{:?}", uv);
+            }
+        }
+    }
+}
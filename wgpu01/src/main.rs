@@ -1,9 +1,78 @@
+mod report;
+
+use std::sync::Arc;
+
+use learn_wgpu_core::config::{self, WgpuConfig};
+use winit::{event_loop::EventLoop, window::Window};
+
+fn usage() -> ! {
+    eprintln!("usage: wgpu01 [--json] [--no-surface] [--backend <list>]");
+    std::process::exit(1);
+}
+
+struct Args {
+    json: bool,
+    surface: bool,
+}
+
+fn parse_args() -> Args {
+    let mut parsed = Args { json: false, surface: true };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => parsed.json = true,
+            "--no-surface" => parsed.surface = false,
+            // handled by WgpuConfig::from_env
+            _ if config::VALUE_FLAGS.contains(&arg.as_str()) => { args.next(); },
+            _ if config::SWITCH_FLAGS.contains(&arg.as_str()) => {},
+            _ => usage(),
+        }
+    }
+    parsed
+}
+
+/// A hidden window, so surface capabilities can be queried. There is none
+/// on headless machines.
+#[allow(deprecated)]
+fn create_window() -> Option<(EventLoop<()>, Arc<Window>)> {
+    let event_loop = EventLoop::new().ok()?;
+    let attributes = Window::default_attributes()
+        .with_visible(false)
+        .with_title("wgpu01");
+    let window = event_loop.create_window(attributes).ok()?;
+    Some((event_loop, Arc::new(window)))
+}
+
 fn main() {
-    let instances = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+    let args = parse_args();
+    let wgpu_config = config::or_exit(WgpuConfig::from_env());
+    let instance = wgpu_config.create_instance();
+
+    // the event loop must outlive the window
+    let window = if args.surface { create_window() } else { None };
+    let surface = window
+        .as_ref()
+        .and_then(|(_, window)| instance.create_surface(window.clone()).ok());
+
     let adapters = pollster::block_on(
-        instances.enumerate_adapters(wgpu::Backends::all())
+        instance.enumerate_adapters(wgpu_config.backends)
     );
-    for adapter in adapters {
-        println!("{:?}", adapter.get_info())
+    let reports: Vec<_> = adapters
+        .iter()
+        .map(|adapter| report::adapter_report(adapter, surface.as_ref()))
+        .collect();
+
+    if args.json {
+        let json = serde_json::json!({ "adapters": reports });
+        println!("{}", serde_json::to_string_pretty(&json).unwrap());
+    } else if reports.is_empty() {
+        println!("No adapters found for backends {:?}", wgpu_config.backends);
+    } else {
+        for (i, report) in reports.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            print!("{}", report::table(i, report));
+        }
     }
 }
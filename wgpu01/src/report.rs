@@ -0,0 +1,255 @@
+//! Collects what an adapter supports into JSON values, which are printed
+//! either as they are or as plain text tables.
+
+use serde_json::{json, Map, Value};
+
+/// Formats whose support is queried. Compressed families are represented by
+/// one format each, since support is reported per family.
+const TEXTURE_FORMATS: &[wgpu::TextureFormat] = &[
+    wgpu::TextureFormat::R8Unorm,
+    wgpu::TextureFormat::R8Snorm,
+    wgpu::TextureFormat::R8Uint,
+    wgpu::TextureFormat::R8Sint,
+    wgpu::TextureFormat::R16Uint,
+    wgpu::TextureFormat::R16Sint,
+    wgpu::TextureFormat::R16Unorm,
+    wgpu::TextureFormat::R16Snorm,
+    wgpu::TextureFormat::R16Float,
+    wgpu::TextureFormat::Rg8Unorm,
+    wgpu::TextureFormat::Rg8Snorm,
+    wgpu::TextureFormat::Rg8Uint,
+    wgpu::TextureFormat::Rg8Sint,
+    wgpu::TextureFormat::R32Uint,
+    wgpu::TextureFormat::R32Sint,
+    wgpu::TextureFormat::R32Float,
+    wgpu::TextureFormat::Rg16Uint,
+    wgpu::TextureFormat::Rg16Sint,
+    wgpu::TextureFormat::Rg16Unorm,
+    wgpu::TextureFormat::Rg16Snorm,
+    wgpu::TextureFormat::Rg16Float,
+    wgpu::TextureFormat::Rgba8Unorm,
+    wgpu::TextureFormat::Rgba8UnormSrgb,
+    wgpu::TextureFormat::Rgba8Snorm,
+    wgpu::TextureFormat::Rgba8Uint,
+    wgpu::TextureFormat::Rgba8Sint,
+    wgpu::TextureFormat::Bgra8Unorm,
+    wgpu::TextureFormat::Bgra8UnormSrgb,
+    wgpu::TextureFormat::Rgb9e5Ufloat,
+    wgpu::TextureFormat::Rgb10a2Uint,
+    wgpu::TextureFormat::Rgb10a2Unorm,
+    wgpu::TextureFormat::Rg11b10Ufloat,
+    wgpu::TextureFormat::R64Uint,
+    wgpu::TextureFormat::Rg32Uint,
+    wgpu::TextureFormat::Rg32Sint,
+    wgpu::TextureFormat::Rg32Float,
+    wgpu::TextureFormat::Rgba16Uint,
+    wgpu::TextureFormat::Rgba16Sint,
+    wgpu::TextureFormat::Rgba16Unorm,
+    wgpu::TextureFormat::Rgba16Snorm,
+    wgpu::TextureFormat::Rgba16Float,
+    wgpu::TextureFormat::Rgba32Uint,
+    wgpu::TextureFormat::Rgba32Sint,
+    wgpu::TextureFormat::Rgba32Float,
+    wgpu::TextureFormat::Stencil8,
+    wgpu::TextureFormat::Depth16Unorm,
+    wgpu::TextureFormat::Depth24Plus,
+    wgpu::TextureFormat::Depth24PlusStencil8,
+    wgpu::TextureFormat::Depth32Float,
+    wgpu::TextureFormat::Depth32FloatStencil8,
+    wgpu::TextureFormat::NV12,
+    wgpu::TextureFormat::Bc1RgbaUnorm,
+    wgpu::TextureFormat::Bc3RgbaUnorm,
+    wgpu::TextureFormat::Bc5RgUnorm,
+    wgpu::TextureFormat::Bc6hRgbUfloat,
+    wgpu::TextureFormat::Bc7RgbaUnorm,
+    wgpu::TextureFormat::Etc2Rgb8Unorm,
+    wgpu::TextureFormat::EacR11Unorm,
+    wgpu::TextureFormat::Astc {
+        block: wgpu::AstcBlock::B4x4,
+        channel: wgpu::AstcChannel::Unorm,
+    },
+];
+
+fn names<T>(flags: impl Iterator<Item = (&'static str, T)>) -> Vec<&'static str> {
+    flags.map(|(name, _)| name).collect()
+}
+
+fn debug_names<T: std::fmt::Debug>(values: &[T]) -> Vec<String> {
+    values.iter().map(|v| format!("{:?}", v)).collect()
+}
+
+/// Everything reported about one adapter. `surface` is only queried when a
+/// window could be created.
+pub fn adapter_report(adapter: &wgpu::Adapter, surface: Option<&wgpu::Surface>) -> Value {
+    let info = adapter.get_info();
+    let downlevel = adapter.get_downlevel_capabilities();
+
+    let formats: Vec<Value> = TEXTURE_FORMATS
+        .iter()
+        .filter_map(|&format| {
+            let features = adapter.get_texture_format_features(format);
+            if features.allowed_usages.is_empty() {
+                return None;
+            }
+            Some(json!({
+                "format": format!("{:?}", format),
+                "usages": names(features.allowed_usages.iter_names()),
+                "flags": names(features.flags.iter_names()),
+                "sample_counts": features.flags.supported_sample_counts(),
+            }))
+        })
+        .collect();
+
+    let surface = surface.map(|surface| {
+        let caps = surface.get_capabilities(adapter);
+        json!({
+            "formats": debug_names(&caps.formats),
+            "present_modes": debug_names(&caps.present_modes),
+            "alpha_modes": debug_names(&caps.alpha_modes),
+            "usages": names(caps.usages.iter_names()),
+        })
+    });
+
+    json!({
+        "info": {
+            "name": info.name,
+            "vendor": info.vendor,
+            "device": info.device,
+            "device_type": format!("{:?}", info.device_type),
+            "backend": format!("{:?}", info.backend),
+            "driver": info.driver,
+            "driver_info": info.driver_info,
+        },
+        "features": names(adapter.features().iter_names()),
+        "limits": serde_json::to_value(adapter.limits()).expect("limits serialize to JSON"),
+        "downlevel": {
+            "shader_model": format!("{:?}", downlevel.shader_model),
+            "webgpu_compliant": downlevel.is_webgpu_compliant(),
+            "flags": names(downlevel.flags.iter_names()),
+        },
+        "texture_formats": formats,
+        "surface": surface,
+    })
+}
+
+fn list(values: &Value) -> String {
+    let items: Vec<String> = values
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|v| match v {
+            Value::String(s) => s.clone(),
+            v => v.to_string(),
+        })
+        .collect();
+    if items.is_empty() { "-".to_string() } else { items.join(" ") }
+}
+
+fn section(out: &mut String, title: &str) {
+    out.push_str(&format!("\n{}\n{}\n", title, "-".repeat(title.len())));
+}
+
+fn rows(out: &mut String, rows: &[(String, String)]) {
+    let width = rows.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+    for (key, value) in rows {
+        out.push_str(&format!("  {:width$}  {}\n", key, value, width = width));
+    }
+}
+
+/// Plain text rendering of [`adapter_report`].
+pub fn table(index: usize, report: &Value) -> String {
+    let mut out = String::new();
+    let info = &report["info"];
+    let title = format!("Adapter {}: {}", index, info["name"].as_str().unwrap_or("?"));
+    out.push_str(&format!("{}\n{}\n", title, "=".repeat(title.len())));
+    let text = |key: &str| match info[key].as_str() {
+        Some(s) if !s.is_empty() => s.to_string(),
+        _ => "-".to_string(),
+    };
+    let hex = |key: &str| format!("{:#06x}", info[key].as_u64().unwrap_or(0));
+    let info_rows = [
+        ("backend".to_string(), text("backend")),
+        ("device type".to_string(), text("device_type")),
+        ("vendor".to_string(), hex("vendor")),
+        ("device".to_string(), hex("device")),
+        ("driver".to_string(), text("driver")),
+        ("driver info".to_string(), text("driver_info")),
+    ];
+    rows(&mut out, &info_rows);
+
+    let features = report["features"].as_array().map_or(0, Vec::len);
+    section(&mut out, &format!("Features ({})", features));
+    for feature in report["features"].as_array().into_iter().flatten() {
+        out.push_str(&format!("  {}\n", feature.as_str().unwrap_or("?")));
+    }
+
+    section(&mut out, "Limits");
+    let limits = report["limits"].as_object().cloned().unwrap_or_else(Map::new);
+    let limit_rows: Vec<(String, String)> = limits
+        .iter()
+        .map(|(key, value)| (key.clone(), value.to_string()))
+        .collect();
+    rows(&mut out, &limit_rows);
+
+    section(&mut out, "Downlevel capabilities");
+    let downlevel = &report["downlevel"];
+    rows(&mut out, &[
+        ("shader model".to_string(), downlevel["shader_model"].as_str().unwrap_or("?").to_string()),
+        ("webgpu compliant".to_string(), downlevel["webgpu_compliant"].to_string()),
+    ]);
+    for flag in downlevel["flags"].as_array().into_iter().flatten() {
+        out.push_str(&format!("  {}\n", flag.as_str().unwrap_or("?")));
+    }
+
+    section(&mut out, "Texture formats");
+    let mut format_rows = vec![("format".to_string(), "samples  usages".to_string())];
+    for format in report["texture_formats"].as_array().into_iter().flatten() {
+        format_rows.push((
+            format["format"].as_str().unwrap_or("?").to_string(),
+            format!("{:7}  {}", list(&format["sample_counts"]), list(&format["usages"])),
+        ));
+    }
+    rows(&mut out, &format_rows);
+
+    section(&mut out, "Surface");
+    let surface = &report["surface"];
+    if surface.is_null() {
+        out.push_str("  no window available\n");
+    } else {
+        rows(&mut out, &[
+            ("formats".to_string(), list(&surface["formats"])),
+            ("present modes".to_string(), list(&surface["present_modes"])),
+            ("alpha modes".to_string(), list(&surface["alpha_modes"])),
+            ("usages".to_string(), list(&surface["usages"])),
+        ]);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_offscreen_adapter() {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = pollster::block_on(
+            instance.request_adapter(&wgpu::RequestAdapterOptions::default())
+        ).expect("no adapter");
+        let report = adapter_report(&adapter, None);
+
+        assert!(report["limits"]["maxTextureDimension2D"].as_u64().unwrap() >= 2048);
+        let rgba = report["texture_formats"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|f| f["format"] == "Rgba8UnormSrgb")
+            .expect("Rgba8UnormSrgb is always supported");
+        assert!(rgba["usages"].as_array().unwrap().contains(&json!("RENDER_ATTACHMENT")));
+        assert!(rgba["sample_counts"].as_array().unwrap().contains(&json!(1)));
+
+        let text = table(0, &report);
+        assert!(text.contains("maxTextureDimension2D"), "{}", text);
+        assert!(text.contains("no window available"), "{}", text);
+    }
+}
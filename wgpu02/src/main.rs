@@ -4,6 +4,7 @@ use winit::{
     window::Window
 };
 
+#[allow(deprecated)]
 fn main() {
     let window_attributes = Window::default_attributes();
     let event_loop = EventLoop::new().unwrap();
@@ -13,12 +14,11 @@ fn main() {
     //env_logger::init();
 
     let _ = event_loop.run(move |event, elwt| {
-        match event {
-            Event::WindowEvent {
-                event: WindowEvent::CloseRequested,
-                ..
-            } => { elwt.exit(); },
-            _ => {}
+        if let Event::WindowEvent {
+            event: WindowEvent::CloseRequested,
+            ..
+        } = event {
+            elwt.exit();
         }
     });
 }
@@ -4,53 +4,69 @@ use winit::{
     window::Window
 };
 
+use learn_wgpu_core::config::{or_exit, InitError, WgpuConfig};
+
 async fn run(event_loop: EventLoop<()>, window: &Window) {
     let size = window.inner_size();
-    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-        backends: wgpu::Backends::VULKAN,
-        ..Default::default()
-    });
-    let surface = instance
-        .create_surface(&window)
-        .expect("Failed to obtain surface");
-    let adapter = instance
-        .request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::default(),
-            compatible_surface: Some(&surface),
-            force_fallback_adapter: false
-        })
-        .await
-        .expect("Failed to find an appropriate adapter");
-
-    let (device, queue) = adapter
-        .request_device(
-            &wgpu::DeviceDescriptor {
-                label: None,
-                required_features: wgpu::Features::empty(),
-                experimental_features: wgpu::ExperimentalFeatures::disabled(),
-                required_limits: wgpu::Limits::default(),
-                memory_hints: Default::default(),
-                trace: wgpu::Trace::Off,
-            },
-        )
-        .await
-        .expect("Failed to create device");
+    let wgpu_config = or_exit(WgpuConfig::from_env());
+    let instance = wgpu_config.create_instance();
+    let surface = or_exit(instance.create_surface(window).map_err(InitError::Surface));
+    let adapter = or_exit(wgpu_config.request_adapter(&instance, Some(&surface)).await);
+    let (device, queue) = or_exit(wgpu_config.request_device(&adapter).await);
 
     let surface_caps = surface.get_capabilities(&adapter);
-    let format = surface_caps.formats[0];
+    let format = wgpu_config.surface_format(&surface_caps);
 
     let mut config = wgpu::SurfaceConfiguration {
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
         format,
         width: size.width,
         height: size.height,
-        present_mode: wgpu::PresentMode::Fifo,
+        present_mode: wgpu_config.present_mode(&surface_caps),
         alpha_mode: surface_caps.alpha_modes[0],
         view_formats: vec![],
         desired_maximum_frame_latency: 2,
     };
     surface.configure(&device, &config);
 
+    let render_pipeline = create_pipeline(&device, format);
+
+    #[allow(deprecated)]
+    let _ = event_loop.run(move |event, elwt| {
+        match event {
+            Event::WindowEvent {
+                event: WindowEvent::Resized(size),
+                ..
+            } => {
+                // Recreate the surface with the new size
+                instance.poll_all(true);
+                config.width = size.width;
+                config.height = size.height;
+                surface.configure(&device, &config);
+            },
+            Event::WindowEvent {
+                event: WindowEvent::RedrawRequested,
+                ..
+            } => {
+                let frame = surface.get_current_texture().unwrap();
+                let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+                draw(&mut encoder, &view, &render_pipeline);
+                queue.submit(Some(encoder.finish()));
+                frame.present();
+            },
+            Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } => elwt.exit(),
+            _ => {}
+        }
+    });
+}
+
+fn create_pipeline(
+    device: &wgpu::Device, format: wgpu::TextureFormat
+) -> wgpu::RenderPipeline {
     // Load the shaders from disk
     let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: None,
@@ -63,7 +79,7 @@ async fn run(event_loop: EventLoop<()>, window: &Window) {
         immediate_size: 0,
     });
 
-    let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
         label: None,
         layout: Some(&pipeline_layout),
         vertex: wgpu::VertexState {
@@ -90,61 +106,36 @@ async fn run(event_loop: EventLoop<()>, window: &Window) {
         multisample: wgpu::MultisampleState::default(),
         cache: None,
         multiview_mask: None,
-    });
+    })
+}
 
-    let _ = event_loop.run(move |event, elwt| {
-        match event {
-            Event::WindowEvent {
-                event: WindowEvent::Resized(size),
-                ..
-            } => {
-                // Recreate the surface with the new size
-                instance.poll_all(true);
-                config.width = size.width;
-                config.height = size.height;
-                surface.configure(&device, &config);
-            },
-            Event::WindowEvent {
-                event: WindowEvent::RedrawRequested,
-                ..
-            } => {
-                let frame = surface.get_current_texture().unwrap();
-                let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
-                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
-                {
-                    let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                        label: None,
-                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                            view: &view,
-                            resolve_target: None,
-                            ops: wgpu::Operations {
-                                load: wgpu::LoadOp::Clear(
-//                                    wgpu::Color {r: 0.05, g: 0.062, b: 0.08, a: 1.0}),
-                                    wgpu::Color {r: 1.0, g: 0.0, b: 1.0, a: 1.0}), // background color
-                                store: wgpu::StoreOp::Store,
-                            },
-                            depth_slice: None,
-                        })],
-                        depth_stencil_attachment: None,
-                        timestamp_writes: None,
-                        occlusion_query_set: None,
-                        multiview_mask: None,
-                    });
-                    rpass.set_pipeline(&render_pipeline);
-                    rpass.draw(0..3, 0..1);
-                }
-                queue.submit(Some(encoder.finish()));
-                frame.present();
+fn draw(
+    encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView,
+    render_pipeline: &wgpu::RenderPipeline
+) {
+    let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: None,
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(
+//                  wgpu::Color {r: 0.05, g: 0.062, b: 0.08, a: 1.0}),
+                    wgpu::Color {r: 1.0, g: 0.0, b: 1.0, a: 1.0}), // background color
+                store: wgpu::StoreOp::Store,
             },
-            Event::WindowEvent {
-                event: WindowEvent::CloseRequested,
-                ..
-            } => elwt.exit(),
-            _ => {}
-        }
+            depth_slice: None,
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+        multiview_mask: None,
     });
+    rpass.set_pipeline(render_pipeline);
+    rpass.draw(0..3, 0..1);
 }
 
+#[allow(deprecated)]
 fn main() {
     let window_attributes = Window::default_attributes();
     let event_loop = EventLoop::new().unwrap();
@@ -154,3 +145,30 @@ fn main() {
 
     pollster::block_on(run(event_loop, &window));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use learn_wgpu_core::{golden, readback, transforms};
+
+    #[test]
+    fn triangle_matches_golden() {
+        let init = pollster::block_on(
+            transforms::InitWgpu::init_offscreen(golden::WIDTH, golden::HEIGHT)
+        );
+        let render_pipeline = create_pipeline(&init.device, init.config.format);
+        let target = init.target.as_ref().unwrap();
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = init.device.create_command_encoder(
+            &wgpu::CommandEncoderDescriptor { label: None }
+        );
+        draw(&mut encoder, &view, &render_pipeline);
+        init.queue.submit(Some(encoder.finish()));
+
+        let pixels = readback::read_texture(&init.device, &init.queue, target);
+        golden::assert_matches(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/golden/triangle.png"),
+            golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+        );
+    }
+}
@@ -3,6 +3,7 @@ mod common;
 use winit::event_loop::EventLoop;
 use winit::window::Window;
 
+#[allow(deprecated)]
 fn main() {
     let mut primitive_type = "point-list";
     let args: Vec<String> = std::env::args().collect();
@@ -22,13 +23,13 @@ fn main() {
 
     let inputs = common::Inputs {
         source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
-        topology: topology,
+        topology,
         strip_index_format: index_format,
     };
     let window_attributes = Window::default_attributes();
     let event_loop = EventLoop::new().unwrap();
     let window = event_loop.create_window(window_attributes).unwrap();
-    window.set_title(&*format!("{}: {}", "Primitive", primitive_type));
+    window.set_title(&format!("{}: {}", "Primitive", primitive_type));
 
     pollster::block_on(common::run(event_loop, &window, inputs, 6));
 }
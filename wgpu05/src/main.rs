@@ -3,13 +3,9 @@ mod common;
 use winit::event_loop::EventLoop;
 use winit::window::Window;
 
-fn main() {
-    let mut primitive_type = "triangle-list";
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() > 1 {
-        primitive_type = &args[1];
-    }
+const NUM_VERTICES: u32 = 9;
 
+fn inputs(primitive_type: &str) -> common::Inputs<'static> {
     let mut topology = wgpu::PrimitiveTopology::TriangleList;
     let mut index_format = None;
     if primitive_type == "triangle-strip" {
@@ -17,15 +13,58 @@ fn main() {
         index_format = Some(wgpu::IndexFormat::Uint32);
     }
 
-    let inputs = common::Inputs {
+    common::Inputs {
         source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
-        topology: topology,
+        topology,
         strip_index_format: index_format,
-    };
+    }
+}
+
+#[allow(deprecated)]
+fn main() {
+    let mut primitive_type = "triangle-list";
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 {
+        primitive_type = &args[1];
+    }
+
+    let inputs = inputs(primitive_type);
     let window_attributes = Window::default_attributes();
     let event_loop = EventLoop::new().unwrap();
     let window = event_loop.create_window(window_attributes).unwrap();
-    window.set_title(&*format!("{}: {}", "Primitive", primitive_type));
+    window.set_title(&format!("{}: {}", "Primitive", primitive_type));
 
-    pollster::block_on(common::run(event_loop, &window, inputs, 9));
+    pollster::block_on(common::run(event_loop, &window, inputs, NUM_VERTICES));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use learn_wgpu_core::{golden, readback, transforms};
+
+    #[test]
+    fn primitives_match_golden() {
+        let init = pollster::block_on(
+            transforms::InitWgpu::init_offscreen(golden::WIDTH, golden::HEIGHT)
+        );
+        let target = init.target.as_ref().unwrap();
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+
+        for primitive_type in ["triangle-list", "triangle-strip"] {
+            let render_pipeline = common::create_pipeline(
+                &init.device, init.config.format, inputs(primitive_type)
+            );
+            let mut encoder = init.device.create_command_encoder(
+                &wgpu::CommandEncoderDescriptor { label: None }
+            );
+            common::draw(&mut encoder, &view, &render_pipeline, NUM_VERTICES);
+            init.queue.submit(Some(encoder.finish()));
+
+            let pixels = readback::read_texture(&init.device, &init.queue, target);
+            golden::assert_matches(
+                format!("{}/golden/{}.png", env!("CARGO_MANIFEST_DIR"), primitive_type),
+                golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+            );
+        }
+    }
 }
@@ -1,6 +1,9 @@
 use std::{iter, mem};
+use std::sync::Arc;
 
 use bytemuck::{Pod, Zeroable};
+use learn_wgpu_core::config::InitError;
+use learn_wgpu_core::transforms;
 use wgpu::util::DeviceExt;
 use winit::{
     event::WindowEvent,
@@ -54,63 +57,28 @@ const VERTICES: &[Vertex] = &[
 ];
 
 pub struct State<'a> {
-    instance: wgpu::Instance,
-    surface: wgpu::Surface<'a>,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    config: wgpu::SurfaceConfiguration,
-    pub size: winit::dpi::PhysicalSize<u32>,
+    pub init: transforms::InitWgpu<'a>,
     pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
 }
 
-impl State<'_> {
-    pub async fn new(window: Window) -> Self {
-        let size = window.inner_size();
-        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::VULKAN,
-            ..Default::default()
-        });
-        let surface = instance
-            .create_surface(window)
-            .expect("Failed to obtain surface");
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false
-            })
-            .await
-            .expect("Failed to find an appropriate adapter");
-
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: None,
-                    required_features: wgpu::Features::empty(),
-                    experimental_features: wgpu::ExperimentalFeatures::disabled(),
-                    required_limits: wgpu::Limits::default(),
-                    memory_hints: Default::default(),
-                    trace: wgpu::Trace::Off,
-                },
-            )
-            .await
-            .expect("Failed to create device");
-
-        let surface_caps = surface.get_capabilities(&adapter);
-        let format = surface_caps.formats[0];
-
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format,
-            width: size.width,
-            height: size.height,
-            present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode: surface_caps.alpha_modes[0],
-            view_formats: vec![],
-            desired_maximum_frame_latency: 2,
-        };
-        surface.configure(&device, &config);
+impl<'a> State<'a> {
+    pub async fn new(window: Arc<Window>) -> Result<Self, InitError> {
+        let init = transforms::InitWgpu::init_wgpu(window).await?;
+        Ok(Self::with_init(init))
+    }
+
+    /// Creates a state that renders into an offscreen texture of the given
+    /// size instead of a window surface.
+    #[cfg(test)]
+    pub async fn new_offscreen(width: u32, height: u32) -> Self {
+        let init = transforms::InitWgpu::init_offscreen(width, height).await;
+        Self::with_init(init)
+    }
+
+    fn with_init(init: transforms::InitWgpu<'a>) -> Self {
+        let device = &init.device;
+        let format = init.config.format;
 
         // Load the shaders from disk
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -164,12 +132,7 @@ impl State<'_> {
         });
 
         Self {
-            instance,
-            surface,
-            device,
-            queue,
-            config,
-            size,
+            init,
             pipeline,
             vertex_buffer
         }
@@ -178,28 +141,25 @@ impl State<'_> {
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
             // Recreate the surface with the new size
-            self.instance.poll_all(true);
-            self.size = new_size;
-            self.config.width = new_size.width;
-            self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
+            self.init.resize(new_size);
         }
     }
 
     #[allow(unused_variables)]
-    fn input(&mut self, event: &WindowEvent) -> bool {
+    pub fn input(&mut self, event: &WindowEvent) -> bool {
         false
     }
 
     pub fn update(&mut self) {}
 
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let frame = self.surface.get_current_texture().unwrap();
+        let frame = self.init.get_current_frame()?;
         let view = frame
-            .texture
+            .texture()
             .create_view(&wgpu::TextureViewDescriptor::default());
 
         let mut encoder = self
+            .init
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("Render Encoder"),
@@ -232,7 +192,7 @@ impl State<'_> {
             rpass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
             rpass.draw(0..6, 0..1);
         }
-        self.queue.submit(iter::once(encoder.finish()));
+        self.init.queue.submit(iter::once(encoder.finish()));
         frame.present();
 
         Ok(())
@@ -1,5 +1,9 @@
 mod common;
 
+use std::sync::Arc;
+
+use learn_wgpu_core::config;
+
 use winit::{
     event::{ElementState, Event, KeyEvent, WindowEvent},
     event_loop::EventLoop,
@@ -7,43 +11,64 @@ use winit::{
     window::Window,
 };
 
+#[allow(deprecated)]
 fn main() {
     let window_attributes = Window::default_attributes();
     let event_loop = EventLoop::new().unwrap();
-    let window = event_loop.create_window(window_attributes).unwrap();
-    window.set_title(&*format!("{}", "Square"));
+    let window = Arc::new(
+        event_loop.create_window(window_attributes).unwrap()
+    );
+    window.set_title("Square");
 
-    let mut state = pollster::block_on(common::State::new(window));
+    let mut state = config::or_exit(pollster::block_on(common::State::new(window)));
     let _ = event_loop.run(move |event, elwt| {
-        match event {
-            Event::WindowEvent {
-                ref event,
-                ..
-            } => {
-                match event {
-                    WindowEvent::CloseRequested
-                    | WindowEvent::KeyboardInput {
-                        event: KeyEvent {
-                            state: ElementState::Pressed,
-                            physical_key: PhysicalKey::Code(KeyCode::Escape),
-                            ..
-                        },
+        if let Event::WindowEvent { ref event, .. } = event {
+            if state.input(event) {
+                return;
+            }
+            match event {
+                WindowEvent::CloseRequested
+                | WindowEvent::KeyboardInput {
+                    event: KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::Escape),
                         ..
-                    } => elwt.exit(),
-                    WindowEvent::RedrawRequested => {
-                        state.update();
-                        match state.render() {
-                            Ok(_) => {},
-                            Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
-                            Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
-                            Err(e) => eprintln!("{:?}", e),
-                        }
                     },
-                    WindowEvent::Resized(size) => state.resize(*size),
-                    _ => {}
-                }
-            },
-            _ => {}
+                    ..
+                } => elwt.exit(),
+                WindowEvent::RedrawRequested => {
+                    state.update();
+                    match state.render() {
+                        Ok(_) => {},
+                        Err(wgpu::SurfaceError::Lost) => state.resize(state.init.size),
+                        Err(wgpu::SurfaceError::OutOfMemory) => elwt.exit(),
+                        Err(e) => eprintln!("{:?}", e),
+                    }
+                },
+                WindowEvent::Resized(size) => state.resize(*size),
+                _ => {}
+            }
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use learn_wgpu_core::{golden, readback};
+
+    #[test]
+    fn square_matches_golden() {
+        let mut state = pollster::block_on(
+            common::State::new_offscreen(golden::WIDTH, golden::HEIGHT)
+        );
+        state.render().unwrap();
+
+        let target = state.init.target.as_ref().unwrap();
+        let pixels = readback::read_texture(&state.init.device, &state.init.queue, target);
+        golden::assert_matches(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/golden/square.png"),
+            golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use std::{iter, mem};
+use std::sync::Arc;
 
 use bytemuck::{Pod, Zeroable};
 use cgmath::*;
@@ -10,8 +11,7 @@ use winit::{
     window::Window
 };
 
-mod vertex_data;
-mod transforms;
+use learn_wgpu_core::{config::{self, InitError}, depth, transforms, vertex_data};
 
 const IS_PERSPECTIVE: bool = true;
 
@@ -63,6 +63,7 @@ impl Vertex {
 
 pub struct State<'a> {
     init: transforms::InitWgpu<'a>,
+    depth: depth::DepthBuffer,
     pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
     uniform_buffer: wgpu::Buffer,
@@ -72,9 +73,21 @@ pub struct State<'a> {
     project_mat: Matrix4<f32>,
 }
 
-impl State<'_> {
-    pub async fn new(window: Window) -> Self {
-        let init = transforms::InitWgpu::init_wgpu(window).await;
+impl<'a> State<'a> {
+    pub async fn new(window: Arc<Window>) -> Result<Self, InitError> {
+        let init = transforms::InitWgpu::init_wgpu(window).await?;
+        Ok(Self::with_init(init))
+    }
+
+    /// Creates a state that renders into an offscreen texture of the given
+    /// size instead of a window surface.
+    #[cfg(test)]
+    pub async fn new_offscreen(width: u32, height: u32) -> Self {
+        let init = transforms::InitWgpu::init_offscreen(width, height).await;
+        Self::with_init(init)
+    }
+
+    fn with_init(init: transforms::InitWgpu<'a>) -> Self {
 
         // Load the shaders from disk
         let shader = init.device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -170,13 +183,7 @@ impl State<'_> {
                 strip_index_format: None,
                 ..Default::default()
             },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth24Plus,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::LessEqual,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-            }),
+            depth_stencil: Some(depth::DepthConfig::default().depth_stencil_state()),
             multisample: wgpu::MultisampleState::default(),
             cache: None,
             multiview_mask: None,
@@ -188,8 +195,13 @@ impl State<'_> {
             usage: wgpu::BufferUsages::VERTEX,
         });
 
+        let depth = depth::DepthBuffer::new(
+            &init.device, init.config.width, init.config.height, Default::default()
+        );
+
         Self {
             init,
+            depth,
             pipeline,
             vertex_buffer,
             uniform_buffer,
@@ -202,12 +214,9 @@ impl State<'_> {
 
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
-            // Recreate the surface with the new size
-            self.init.instance.poll_all(true);
-            self.init.size = new_size;
-            self.init.config.width = new_size.width;
-            self.init.config.height = new_size.height;
-            self.init.surface.configure(&self.init.device, &self.init.config);
+            // Recreate the surface and depth buffer with the new size
+            self.init.resize(new_size);
+            self.depth.resize(&self.init.device, new_size.width, new_size.height);
 
             self.project_mat = transforms::create_projection(
                 new_size.width as f32 / new_size.height as f32, IS_PERSPECTIVE
@@ -221,35 +230,17 @@ impl State<'_> {
     }
 
     #[allow(unused_variables)]
-    fn input(&mut self, event: &WindowEvent) -> bool {
+    pub fn input(&mut self, event: &WindowEvent) -> bool {
         false
     }
 
     pub fn update(&mut self) {}
 
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let frame = self.init.surface.get_current_texture().unwrap();
+        let frame = self.init.get_current_frame()?;
         let view = frame
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-        let depth_texture = self.init.device.create_texture(
-            &wgpu::TextureDescriptor {
-                size: wgpu::Extent3d {
-                    width: self.init.config.width,
-                    height: self.init.config.height,
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Depth24Plus,
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                label: None,
-                view_formats: &[],
-            });
-        let depth_view = depth_texture
+            .texture()
             .create_view(&wgpu::TextureViewDescriptor::default());
-
         let mut encoder = self
             .init
             .device
@@ -275,16 +266,7 @@ impl State<'_> {
                     },
                     depth_slice: None,
                 })],
-                depth_stencil_attachment: Some(
-                    wgpu::RenderPassDepthStencilAttachment {
-                        view: &depth_view,
-                        depth_ops: Some(wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(1.0),
-                            store: wgpu::StoreOp::Discard,
-                        }),
-                        stencil_ops: None
-                    }
-                ),
+                depth_stencil_attachment: Some(self.depth.attachment()),
                 timestamp_writes: None,
                 occlusion_query_set: None,
                 multiview_mask: None,
@@ -301,45 +283,66 @@ impl State<'_> {
     }
 }
 
+#[allow(deprecated)]
 fn main() {
     let window_attributes = Window::default_attributes();
     let event_loop = EventLoop::new().unwrap();
-    let window = event_loop.create_window(window_attributes).unwrap();
-    window.set_title(&*format!("{}", "Cube with distinct face colors"));
+    let window = Arc::new(
+        event_loop.create_window(window_attributes).unwrap()
+    );
+    window.set_title("Cube with distinct face colors");
 
-    let mut state = pollster::block_on(State::new(window));
+    let mut state = config::or_exit(pollster::block_on(State::new(window)));
     let _ = event_loop.run(move |event, elwt| {
-        match event {
-            Event::WindowEvent {
-                ref event,
-                ..
-            } => {
-                match event {
-                    WindowEvent::CloseRequested
-                    | WindowEvent::KeyboardInput {
-                        event: KeyEvent {
-                            state: ElementState::Pressed,
-                            physical_key: PhysicalKey::Code(KeyCode::Escape),
-                            ..
-                        },
+        if let Event::WindowEvent { ref event, .. } = event {
+            if state.input(event) {
+                return;
+            }
+            match event {
+                WindowEvent::CloseRequested
+                | WindowEvent::KeyboardInput {
+                    event: KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::Escape),
                         ..
-                    } => elwt.exit(),
-                    WindowEvent::RedrawRequested => {
-                        state.update();
-                        match state.render() {
-                            Ok(_) => {},
-                            Err(wgpu::SurfaceError::Lost)
-                                => state.resize(state.init.size),
-                            Err(wgpu::SurfaceError::OutOfMemory)
-                                => elwt.exit(),
-                            Err(e) => eprintln!("{:?}", e),
-                        }
                     },
-                    WindowEvent::Resized(size) => state.resize(*size),
-                    _ => {}
-                }
-            },
-            _ => {}
+                    ..
+                } => elwt.exit(),
+                WindowEvent::RedrawRequested => {
+                    state.update();
+                    match state.render() {
+                        Ok(_) => {},
+                        Err(wgpu::SurfaceError::Lost)
+                            => state.resize(state.init.size),
+                        Err(wgpu::SurfaceError::OutOfMemory)
+                            => elwt.exit(),
+                        Err(e) => eprintln!("{:?}", e),
+                    }
+                },
+                WindowEvent::Resized(size) => state.resize(*size),
+                _ => {}
+            }
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use learn_wgpu_core::{golden, readback};
+
+    #[test]
+    fn cube_matches_golden() {
+        let mut state = pollster::block_on(
+            State::new_offscreen(golden::WIDTH, golden::HEIGHT)
+        );
+        state.render().unwrap();
+
+        let target = state.init.target.as_ref().unwrap();
+        let pixels = readback::read_texture(&state.init.device, &state.init.queue, target);
+        golden::assert_matches(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/golden/cube.png"),
+            golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+        );
+    }
+}
@@ -11,8 +11,7 @@ use winit::{
     window::Window
 };
 
-mod vertex_data;
-mod transforms;
+use learn_wgpu_core::{config::{self, InitError}, depth, transforms, vertex_data};
 
 const IS_PERSPECTIVE: bool = true;
 const ANIMATION_SPEED: f32 = 1.0;
@@ -65,6 +64,7 @@ impl Vertex {
 
 pub struct State<'a> {
     init: transforms::InitWgpu<'a>,
+    depth: depth::DepthBuffer,
     pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
     uniform_buffer: wgpu::Buffer,
@@ -76,8 +76,8 @@ pub struct State<'a> {
 }
 
 impl State<'_> {
-    pub async fn new(window: Arc<Window>) -> Self {
-        let init = transforms::InitWgpu::init_wgpu(window.clone()).await;
+    pub async fn new(window: Arc<Window>) -> Result<Self, InitError> {
+        let init = transforms::InitWgpu::init_wgpu(window.clone()).await?;
 
         // Load the shaders from disk
         let shader = init.device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -173,13 +173,7 @@ impl State<'_> {
                 strip_index_format: None,
                 ..Default::default()
             },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth24Plus,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::LessEqual,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-            }),
+            depth_stencil: Some(depth::DepthConfig::default().depth_stencil_state()),
             multisample: wgpu::MultisampleState::default(),
             cache: None,
             multiview_mask: None,
@@ -191,8 +185,13 @@ impl State<'_> {
             usage: wgpu::BufferUsages::VERTEX,
         });
 
-        Self {
+        let depth = depth::DepthBuffer::new(
+            &init.device, init.config.width, init.config.height, Default::default()
+        );
+
+        Ok(Self {
             init,
+            depth,
             pipeline,
             vertex_buffer,
             uniform_buffer,
@@ -201,17 +200,14 @@ impl State<'_> {
             view_mat,
             project_mat,
             window,
-        }
+        })
     }
 
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
-            // Recreate the surface with the new size
-            self.init.instance.poll_all(true);
-            self.init.size = new_size;
-            self.init.config.width = new_size.width;
-            self.init.config.height = new_size.height;
-            self.init.surface.configure(&self.init.device, &self.init.config);
+            // Recreate the surface and depth buffer with the new size
+            self.init.resize(new_size);
+            self.depth.resize(&self.init.device, new_size.width, new_size.height);
 
             self.project_mat = transforms::create_projection(
                 new_size.width as f32 / new_size.height as f32, IS_PERSPECTIVE
@@ -225,7 +221,7 @@ impl State<'_> {
     }
 
     #[allow(unused_variables)]
-    fn input(&mut self, event: &WindowEvent) -> bool {
+    pub fn input(&mut self, event: &WindowEvent) -> bool {
         false
     }
 
@@ -246,28 +242,10 @@ impl State<'_> {
 
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
         self.window.request_redraw();
-        let frame = self.init.surface.get_current_texture().unwrap();
+        let frame = self.init.get_current_frame()?;
         let view = frame
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-        let depth_texture = self.init.device.create_texture(
-            &wgpu::TextureDescriptor {
-                size: wgpu::Extent3d {
-                    width: self.init.config.width,
-                    height: self.init.config.height,
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Depth24Plus,
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                label: None,
-                view_formats: &[],
-            });
-        let depth_view = depth_texture
+            .texture()
             .create_view(&wgpu::TextureViewDescriptor::default());
-
         let mut encoder = self
             .init
             .device
@@ -293,16 +271,7 @@ impl State<'_> {
                     },
                     depth_slice: None,
                 })],
-                depth_stencil_attachment: Some(
-                    wgpu::RenderPassDepthStencilAttachment {
-                        view: &depth_view,
-                        depth_ops: Some(wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(1.0),
-                            store: wgpu::StoreOp::Discard,
-                        }),
-                        stencil_ops: None
-                    }
-                ),
+                depth_stencil_attachment: Some(self.depth.attachment()),
                 timestamp_writes: None,
                 occlusion_query_set: None,
                 multiview_mask: None,
@@ -319,51 +288,49 @@ impl State<'_> {
     }
 }
 
+#[allow(deprecated)]
 fn main() {
     let window_attributes = Window::default_attributes();
     let event_loop = EventLoop::new().unwrap();
     let window = Arc::new(
         event_loop.create_window(window_attributes).unwrap()
     );
-    window.set_title(&*format!("{}", "Cube with distinct face colors"));
+    window.set_title("Cube with distinct face colors");
 
-    let mut state = pollster::block_on(State::new(window));
+    let mut state = config::or_exit(pollster::block_on(State::new(window)));
 
     let start_time = std::time::Instant::now();
     let _ = event_loop.run(move |event, elwt| {
-        match event {
-            Event::WindowEvent {
-                ref event,
-                ..
-            } => {
-                match event {
-                    WindowEvent::CloseRequested
-                    | WindowEvent::KeyboardInput {
-                        event: KeyEvent {
-                            state: ElementState::Pressed,
-                            physical_key: PhysicalKey::Code(KeyCode::Escape),
-                            ..
-                        },
+        if let Event::WindowEvent { ref event, .. } = event {
+            if state.input(event) {
+                return;
+            }
+            match event {
+                WindowEvent::CloseRequested
+                | WindowEvent::KeyboardInput {
+                    event: KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::Escape),
                         ..
-                    } => elwt.exit(),
-                    WindowEvent::RedrawRequested => {
-                        let now = std::time::Instant::now();
-                        let dt = now - start_time;
-                        state.update(dt);
-                        match state.render() {
-                            Ok(_) => {},
-                            Err(wgpu::SurfaceError::Lost)
-                                => state.resize(state.init.size),
-                            Err(wgpu::SurfaceError::OutOfMemory)
-                                => elwt.exit(),
-                            Err(e) => eprintln!("{:?}", e),
-                        }
                     },
-                    WindowEvent::Resized(size) => state.resize(*size),
-                    _ => {}
-                }
-            },
-            _ => {}
+                    ..
+                } => elwt.exit(),
+                WindowEvent::RedrawRequested => {
+                    let now = std::time::Instant::now();
+                    let dt = now - start_time;
+                    state.update(dt);
+                    match state.render() {
+                        Ok(_) => {},
+                        Err(wgpu::SurfaceError::Lost)
+                            => state.resize(state.init.size),
+                        Err(wgpu::SurfaceError::OutOfMemory)
+                            => elwt.exit(),
+                        Err(e) => eprintln!("{:?}", e),
+                    }
+                },
+                WindowEvent::Resized(size) => state.resize(*size),
+                _ => {}
+            }
         }
     });
 }
@@ -1,18 +1,12 @@
 use std::{iter, mem};
 use std::sync::Arc;
 
-use bytemuck::{ Pod, Zeroable, cast_slice };
-use cgmath::{ Matrix, Matrix4, SquareMatrix };
+use bytemuck::{ Pod, Zeroable };
+use cgmath::Matrix4;
 use wgpu::util::DeviceExt;
-use winit::{
-    event::{ElementState, Event, KeyEvent, WindowEvent},
-    event_loop::EventLoop,
-    keyboard::{KeyCode, PhysicalKey},
-    window::Window
-};
+use winit::{event::WindowEvent, window::Window};
 
-#[path="../src/transforms.rs"]
-mod transforms;
+use learn_wgpu_core::{app, camera, config::{self, InitError}, depth, screenshot, transforms};
 
 const IS_PERSPECTIVE : bool = true;
 const ANIMATION_SPEED : f32 = 1.0;
@@ -38,19 +32,26 @@ impl Vertex {
 
 pub struct State<'a> {
     init: transforms::InitWgpu<'a>,
+    depth: depth::DepthBuffer,
     pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
     uniform_buffer: wgpu::Buffer,
     uniform_bind_group: wgpu::BindGroup,
     model_mat: Matrix4<f32>,
+    camera: camera::Camera,
+    camera_controller: camera::CameraController,
     view_mat: Matrix4<f32>,
     project_mat: Matrix4<f32>,
+    last_update: std::time::Duration,
     window: Arc<Window>,
+    screenshot: screenshot::Pending,
 }
 
 impl State<'_> {
-    pub async fn new(window: Arc<Window>, vertex_data: &Vec<Vertex>) -> Self {
-        let init = transforms::InitWgpu::init_wgpu(window.clone()).await;
+    pub async fn new(
+        window: Arc<Window>, vertex_data: &[Vertex]
+    ) -> Result<Self, InitError> {
+        let init = transforms::InitWgpu::init_wgpu(window.clone()).await?;
 
         // Load the shaders from disk
         let shader = init.device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -146,13 +147,7 @@ impl State<'_> {
                 strip_index_format: None,
                 ..Default::default()
             },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth24Plus,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::LessEqual,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-            }),
+            depth_stencil: Some(depth::DepthConfig::default().depth_stencil_state()),
             multisample: wgpu::MultisampleState::default(),
             cache: None,
             multiview_mask: None,
@@ -160,31 +155,37 @@ impl State<'_> {
 
         let vertex_buffer = init.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Vertex Buffer"),
-            contents: bytemuck::cast_slice(&vertex_data),
+            contents: bytemuck::cast_slice(vertex_data),
             usage: wgpu::BufferUsages::VERTEX,
         });
 
-        Self {
+        let depth = depth::DepthBuffer::new(
+            &init.device, init.config.width, init.config.height, Default::default()
+        );
+
+        Ok(Self {
             init,
+            depth,
             pipeline,
             vertex_buffer,
             uniform_buffer,
             uniform_bind_group,
             model_mat,
+            camera: camera::Camera::new(camera_position, look_direction, up_direction),
+            camera_controller: camera::CameraController::default(),
             view_mat,
             project_mat,
+            last_update: std::time::Duration::ZERO,
             window,
-        }
+            screenshot: screenshot::Pending::default(),
+        })
     }
 
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
-            // Recreate the surface with the new size
-            self.init.instance.poll_all(true);
-            self.init.size = new_size;
-            self.init.config.width = new_size.width;
-            self.init.config.height = new_size.height;
-            self.init.surface.configure(&self.init.device, &self.init.config);
+            // Recreate the surface and depth buffer with the new size
+            self.init.resize(new_size);
+            self.depth.resize(&self.init.device, new_size.width, new_size.height);
 
             self.project_mat = transforms::create_projection(
                 new_size.width as f32 / new_size.height as f32, IS_PERSPECTIVE
@@ -197,12 +198,16 @@ impl State<'_> {
         }
     }
 
-    #[allow(unused_variables)]
-    fn input(&mut self, event: &WindowEvent) -> bool {
-        false
+    pub fn input(&mut self, event: &WindowEvent) -> bool {
+        self.camera_controller.process_event(&mut self.camera, event, self.init.size)
     }
 
     pub fn update(&mut self, dt: std::time::Duration) {
+        // move the camera
+        self.camera_controller.update(&mut self.camera, dt.saturating_sub(self.last_update));
+        self.last_update = dt;
+        self.view_mat = self.camera.view_mat();
+
         // update uniform buffer
         let dt = ANIMATION_SPEED * dt.as_secs_f32();
         let model_mat = transforms::create_transforms(
@@ -219,28 +224,10 @@ impl State<'_> {
 
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
         self.window.request_redraw();
-        let frame = self.init.surface.get_current_texture().unwrap();
+        let frame = self.init.get_current_frame()?;
         let view = frame
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-        let depth_texture = self.init.device.create_texture(
-            &wgpu::TextureDescriptor {
-                size: wgpu::Extent3d {
-                    width: self.init.config.width,
-                    height: self.init.config.height,
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Depth24Plus,
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                label: None,
-                view_formats: &[],
-            });
-        let depth_view = depth_texture
+            .texture()
             .create_view(&wgpu::TextureViewDescriptor::default());
-
         let mut encoder = self
             .init
             .device
@@ -266,16 +253,7 @@ impl State<'_> {
                     },
                     depth_slice: None,
                 })],
-                depth_stencil_attachment: Some(
-                    wgpu::RenderPassDepthStencilAttachment {
-                        view: &depth_view,
-                        depth_ops: Some(wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(1.0),
-                            store: wgpu::StoreOp::Discard,
-                        }),
-                        stencil_ops: None
-                    }
-                ),
+                depth_stencil_attachment: Some(self.depth.attachment()),
                 timestamp_writes: None,
                 occlusion_query_set: None,
                 multiview_mask: None,
@@ -286,59 +264,49 @@ impl State<'_> {
             rpass.draw(0..36, 0..1);
         }
         self.init.queue.submit(iter::once(encoder.finish()));
+        self.screenshot.save_requested(
+            &self.init.device, &self.init.queue, frame.texture(), &self.init.config
+        );
         frame.present();
 
         Ok(())
     }
 }
 
-pub fn run(vertex_data: &Vec<Vertex>, title: &str) {
-    let window_attributes = Window::default_attributes();
-    let event_loop = EventLoop::new().unwrap();
-    let window = Arc::new(
-        event_loop.create_window(window_attributes).unwrap()
-    );
-    window.set_title(&*format!("{}", title));
-
-    let mut state = pollster::block_on(
-        State::new(window, &vertex_data)
-    );
-
-    let start_time = std::time::Instant::now();
-    let _ = event_loop.run(move |event, elwt| {
-        match event {
-            Event::WindowEvent {
-                ref event,
-                ..
-            } => {
-                match event {
-                    WindowEvent::CloseRequested
-                    | WindowEvent::KeyboardInput {
-                        event: KeyEvent {
-                            state: ElementState::Pressed,
-                            physical_key: PhysicalKey::Code(KeyCode::Escape),
-                            ..
-                        },
-                        ..
-                    } => elwt.exit(),
-                    WindowEvent::RedrawRequested => {
-                        let now = std::time::Instant::now();
-                        let dt = now - start_time;
-                        state.update(dt);
-                        match state.render() {
-                            Ok(_) => {},
-                            Err(wgpu::SurfaceError::Lost)
-                                => state.resize(state.init.size),
-                            Err(wgpu::SurfaceError::OutOfMemory)
-                                => elwt.exit(),
-                            Err(e) => eprintln!("{:?}", e),
-                        }
-                    },
-                    WindowEvent::Resized(size) => state.resize(*size),
-                    _ => {}
-                }
-            },
-            _ => {}
-        }
-    });
+impl app::App for State<'_> {
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        State::input(self, event)
+    }
+
+    fn update(&mut self, dt: std::time::Duration) {
+        State::update(self, dt);
+    }
+
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        State::render(self)
+    }
+
+    fn resize(&mut self, size: winit::dpi::PhysicalSize<u32>) {
+        State::resize(self, size);
+    }
+
+    fn size(&self) -> winit::dpi::PhysicalSize<u32> {
+        self.init.size
+    }
+
+    fn screenshot(&mut self) -> &mut screenshot::Pending {
+        &mut self.screenshot
+    }
+}
+
+pub fn run(vertex_data: &[Vertex], title: &str) {
+    // exit on a mistyped flag before a window opens
+    let screenshot_args = config::or_exit(screenshot::ScreenshotArgs::from_env());
+
+    let (event_loop, window) = app::create_window(title);
+    let state = config::or_exit(pollster::block_on(
+        State::new(window, vertex_data)
+    ));
+
+    app::run_loop(event_loop, state, screenshot_args);
 }
@@ -1,6 +1,6 @@
 mod common;
-mod vertex_data;
-mod transforms;
+
+use learn_wgpu_core::vertex_data;
 
 fn vertex(p: [i8; 3], c: [i8; 3]) -> common::Vertex {
     common::Vertex {
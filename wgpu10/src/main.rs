@@ -1,6 +1,4 @@
-mod common;
-mod vertex_data;
-mod transforms;
+use learn_wgpu_core::{common, vertex_data};
 
 fn vertex(p: [i8; 3], n: [i8; 3]) -> common::Vertex {
     common::Vertex {
@@ -32,7 +30,7 @@ fn create_vertices() -> Vec<common::Vertex> {
 
 fn main() {
     let vertex_data = create_vertices();
-    let light_data = common::light(
+    let material = common::material(
         [1.0, 0.0, 1.0],
         [1.0, 1.0, 0.0],
         0.1,
@@ -40,5 +38,34 @@ fn main() {
         0.7,
         30.0
     );
-    common::run(&vertex_data, light_data, "Cube with Lightning");
+    common::run(&vertex_data, material, "Cube with Lightning");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use learn_wgpu_core::golden;
+
+    #[test]
+    fn lit_cube_matches_golden() {
+        let vertex_data = create_vertices();
+        let material = common::material(
+            [1.0, 0.0, 1.0],
+            [1.0, 1.0, 0.0],
+            0.1,
+            0.6,
+            0.7,
+            30.0
+        );
+        let mut state = pollster::block_on(common::State::new_offscreen(
+            golden::WIDTH, golden::HEIGHT, &vertex_data, material
+        ));
+        state.update(std::time::Duration::from_secs(1));
+
+        let pixels = state.render_to_rgba().unwrap();
+        golden::assert_matches(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/golden/lit-cube.png"),
+            golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+        );
+    }
 }
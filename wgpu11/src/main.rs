@@ -1,36 +1,13 @@
-mod common;
-mod vertex_data;
-mod transforms;
+use learn_wgpu_core::{common, mesh, vertex_data};
 
-fn vertex(p: [f32; 3], n: [f32; 3]) -> common::Vertex {
-    common::Vertex {
-        position: [
-            p[0],
-            p[1],
-            p[2],
-            1.0
-        ],
-        normal: [
-            n[0],
-            n[1],
-            n[2],
-            1.0
-        ],
-    }
-}
-
-fn create_vertices(r: f32, u: usize, v: usize) -> Vec<common::Vertex> {
-    let (pos, normal, _uvs) = vertex_data::sphere_data(r, u, v);
-    let mut data: Vec<common::Vertex> = Vec::with_capacity(pos.len());
-    for i in 0..pos.len() {
-        data.push(vertex(pos[i], normal[i]));
-    }
-    data.to_vec()
+fn create_mesh(r: f32, u: usize, v: usize) -> mesh::IndexedMesh {
+    let (pos, normal, indices) = vertex_data::sphere_indexed(r, u, v);
+    mesh::IndexedMesh::new(&pos, &normal, indices)
 }
 
 fn main() {
-    let vertex_data = create_vertices(2.0, 15, 20);
-    let light_data = common::light(
+    let mesh = create_mesh(2.0, 15, 20);
+    let material = common::material(
         [1.0, 0.0, 1.0],
         [1.0, 1.0, 0.0],
         0.1,
@@ -38,5 +15,38 @@ fn main() {
         0.7,
         30.0
     );
-    common::run(&vertex_data, light_data, "Sphere with Lightning");
+    common::run_scene(
+        &[common::SceneObject::indexed(&mesh, material)],
+        "Sphere with Lightning"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use learn_wgpu_core::golden;
+
+    #[test]
+    fn lit_sphere_matches_golden() {
+        let mesh = create_mesh(2.0, 15, 20);
+        let material = common::material(
+            [1.0, 0.0, 1.0],
+            [1.0, 1.0, 0.0],
+            0.1,
+            0.6,
+            0.7,
+            30.0
+        );
+        let mut state = pollster::block_on(common::State::new_offscreen_scene(
+            golden::WIDTH, golden::HEIGHT,
+            &[common::SceneObject::indexed(&mesh, material)]
+        ));
+        state.update(std::time::Duration::from_secs(1));
+
+        let pixels = state.render_to_rgba().unwrap();
+        golden::assert_matches(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/golden/lit-sphere.png"),
+            golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+        );
+    }
 }
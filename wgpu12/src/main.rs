@@ -1,40 +1,17 @@
-mod common;
-mod vertex_data;
-mod transforms;
-
-fn vertex(p: [f32; 3], n: [f32; 3]) -> common::Vertex {
-    common::Vertex {
-        position: [
-            p[0],
-            p[1],
-            p[2],
-            1.0
-        ],
-        normal: [
-            n[0],
-            n[1],
-            n[2],
-            1.0
-        ],
-    }
-}
+use learn_wgpu_core::{common, mesh, vertex_data};
 
-fn create_vertices(
+fn create_mesh(
     r_torus: f32, r_tube: f32, n_torus: usize, n_tube: usize
-) -> Vec<common::Vertex> {
-    let (pos, normal) = vertex_data::torus_data(
+) -> mesh::IndexedMesh {
+    let (pos, normal, indices) = vertex_data::torus_indexed(
         r_torus, r_tube, n_torus, n_tube
     );
-    let mut data: Vec<common::Vertex> = Vec::with_capacity(pos.len());
-    for i in 0..pos.len() {
-        data.push(vertex(pos[i], normal[i]));
-    }
-    data.to_vec()
+    mesh::IndexedMesh::new(&pos, &normal, indices)
 }
 
 fn main() {
-    let vertex_data = create_vertices(1.8, 0.4, 180, 50);
-    let light_data = common::light(
+    let mesh = create_mesh(1.8, 0.4, 180, 50);
+    let material = common::material(
         [1.0, 0.0, 1.0],
         [1.0, 1.0, 0.0],
         0.1,
@@ -42,5 +19,69 @@ fn main() {
         0.7,
         30.0
     );
-    common::run(&vertex_data, light_data, "Sphere with Lightning");
+    common::run_scene(
+        &[common::SceneObject::indexed(&mesh, material)],
+        "Sphere with Lightning"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use learn_wgpu_core::golden;
+
+    fn create_state() -> common::State<'static> {
+        let mesh = create_mesh(1.8, 0.4, 180, 50);
+        let material = common::material(
+            [1.0, 0.0, 1.0],
+            [1.0, 1.0, 0.0],
+            0.1,
+            0.6,
+            0.7,
+            30.0
+        );
+        pollster::block_on(common::State::new_offscreen_scene(
+            golden::WIDTH, golden::HEIGHT,
+            &[common::SceneObject::indexed(&mesh, material)]
+        ))
+    }
+
+    #[test]
+    fn lit_torus_matches_golden() {
+        let mut state = create_state();
+        state.update(std::time::Duration::from_secs(1));
+
+        let pixels = state.render_to_rgba().unwrap();
+        golden::assert_matches(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/golden/lit-torus.png"),
+            golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+        );
+    }
+
+    #[test]
+    fn multisampled_torus_matches_golden() {
+        let mut state = create_state();
+        // 4x is supported everywhere, other counts depend on the adapter
+        let supported = state.supported_sample_counts();
+        assert!(supported.contains(&1) && supported.contains(&4), "{:?}", supported);
+        let error = state.set_sample_count(3).unwrap_err();
+        assert_eq!(error.requested, 3);
+        state.update(std::time::Duration::from_secs(1));
+        let aliased = state.render_to_rgba().unwrap();
+
+        state.set_sample_count(4).unwrap();
+        let pixels = state.render_to_rgba().unwrap();
+        assert_ne!(pixels, aliased);
+        golden::assert_matches(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/golden/lit-torus-msaa4x.png"),
+            golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+        );
+
+        // cycling visits every supported count, wrapping around to 1
+        let start = supported.iter().position(|&count| count == 4).unwrap();
+        let cycled: Vec<u32> = supported.iter().map(|_| state.cycle_sample_count()).collect();
+        let expected: Vec<u32> =
+            supported.iter().cycle().skip(start + 1).take(supported.len()).copied().collect();
+        assert_eq!(cycled, expected);
+    }
 }
@@ -1,17 +0,0 @@
-use cgmath::*;
-
-pub fn sphere_position(r: f32, theta: Deg<f32>, phi: Deg<f32>) -> [f32; 3] {
-    let x = r * theta.sin() * phi.cos();
-    let y = r * theta.cos();
-    let z = -r * theta.sin() * phi.sin();
-    [x, y, z]
-}
-
-pub fn torus_position(
-    r_torus: f32, r_tube: f32, u: Deg<f32>, v: Deg<f32>
-) -> [f32; 3] {
-    let x = (r_torus + r_tube + v.cos()) * u.cos();
-    let y = r_tube * v.sin();
-    let z = -(r_torus + r_tube + v.cos()) * u.sin();
-    [x, y, z]
-}
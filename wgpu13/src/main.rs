@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+
+use learn_wgpu_core::{args, common, config, gltf_scene, obj};
+use learn_wgpu_core::material::Material;
+
+const DEFAULT_MODEL: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/pyramid.obj");
+
+fn parse_args() -> (PathBuf, obj::NormalMode) {
+    let args = config::or_exit(
+        args::ModelArgs::from_env("wgpu13 [model.obj] [--flat]", &["--flat"])
+    );
+    let normal_mode = if args.has("--flat") {
+        obj::NormalMode::Flat
+    } else {
+        obj::NormalMode::Smooth
+    };
+    (args.path.unwrap_or_else(|| DEFAULT_MODEL.into()), normal_mode)
+}
+
+/// Uses the first mesh's MTL material when available.
+fn mtl_material(model: &obj::ObjModel) -> Material {
+    let material = model.meshes
+        .first()
+        .and_then(|mesh| mesh.material.as_deref())
+        .and_then(|name| model.material(name));
+    match material {
+        Some(m) => common::material(m.diffuse, m.specular, 0.1, 0.6, 0.7, m.shininess),
+        None => common::material(
+            [1.0, 0.0, 1.0],
+            [1.0, 1.0, 0.0],
+            0.1,
+            0.6,
+            0.7,
+            30.0
+        ),
+    }
+}
+
+fn load(path: &PathBuf, normal_mode: obj::NormalMode) -> common::SceneObject {
+    let model = match obj::load_obj(path, normal_mode) {
+        Ok(model) => model,
+        Err(e) => {
+            eprintln!("Failed to load {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    };
+    let mut object = common::SceneObject::new(&model.vertices(), mtl_material(&model));
+    gltf_scene::fit_to_view(std::slice::from_mut(&mut object), 2.0);
+    object
+}
+
+fn main() {
+    let (path, normal_mode) = parse_args();
+    let object = load(&path, normal_mode);
+    let title = format!("OBJ model: {}", path.display());
+    common::run_scene(&[object], &title);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use learn_wgpu_core::golden;
+
+    #[test]
+    fn pyramid_matches_golden() {
+        let object = load(&DEFAULT_MODEL.into(), obj::NormalMode::Flat);
+        let mut state = pollster::block_on(common::State::new_offscreen_scene(
+            golden::WIDTH, golden::HEIGHT, &[object]
+        ));
+        state.update(std::time::Duration::ZERO);
+
+        let pixels = state.render_to_rgba().unwrap();
+        golden::assert_matches(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/golden/pyramid.png"),
+            golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+        );
+    }
+}
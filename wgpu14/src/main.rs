@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use learn_wgpu_core::{args, common, config, gltf_scene};
+
+const DEFAULT_MODEL: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/pedestal.gltf");
+
+fn parse_args() -> PathBuf {
+    let args = config::or_exit(
+        args::ModelArgs::from_env("wgpu14 [scene.gltf | scene.glb]", &[])
+    );
+    args.path.unwrap_or_else(|| DEFAULT_MODEL.into())
+}
+
+fn load(path: &PathBuf) -> Vec<common::SceneObject> {
+    let mut objects = match gltf_scene::load_gltf(path) {
+        Ok(objects) => objects,
+        Err(e) => {
+            eprintln!("Failed to load {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    };
+    gltf_scene::fit_to_view(&mut objects, 2.0);
+    objects
+}
+
+fn main() {
+    let path = parse_args();
+    let objects = load(&path);
+    let title = format!("glTF scene: {}", path.display());
+    common::run_scene(&objects, &title);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use learn_wgpu_core::golden;
+
+    #[test]
+    fn pedestal_matches_golden() {
+        let objects = load(&DEFAULT_MODEL.into());
+        assert_eq!(objects.len(), 2);
+        let mut state = pollster::block_on(common::State::new_offscreen_scene(
+            golden::WIDTH, golden::HEIGHT, &objects
+        ));
+        state.update(std::time::Duration::ZERO);
+
+        let pixels = state.render_to_rgba().unwrap();
+        golden::assert_matches(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/golden/pedestal.png"),
+            golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use learn_wgpu_core::{common, texture, vertex_data};
+
+const TEXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/globe.png");
+
+fn vertex(p: [f32; 3], n: [f32; 3], uv: [f32; 2]) -> common::TexturedVertex {
+    common::TexturedVertex {
+        position: [p[0], p[1], p[2], 1.0],
+        normal: [n[0], n[1], n[2], 1.0],
+        uv,
+    }
+}
+
+fn create_vertices(r: f32, u: usize, v: usize) -> Vec<common::TexturedVertex> {
+    let (pos, normal, uvs) = vertex_data::sphere_data(r, u, v);
+    pos.iter()
+        .zip(normal.iter())
+        .zip(uvs.iter())
+        .map(|((p, n), uv)| vertex(*p, *n, *uv))
+        .collect()
+}
+
+fn create_globe() -> common::SceneObject {
+    let vertex_data = create_vertices(2.0, 31, 61);
+    let image = texture::load_image(TEXTURE).expect("Failed to load texture");
+    // a white material keeps the texture colors
+    let material = common::material(
+        [1.0, 1.0, 1.0],
+        [1.0, 1.0, 1.0],
+        0.2,
+        0.8,
+        0.3,
+        30.0
+    );
+    common::SceneObject::textured(&vertex_data, material.with_diffuse_texture(image))
+}
+
+fn main() {
+    common::run_scene(&[create_globe()], "Textured Sphere");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use learn_wgpu_core::golden;
+
+    #[test]
+    fn textured_sphere_matches_golden() {
+        let mut state = pollster::block_on(common::State::new_offscreen_scene(
+            golden::WIDTH, golden::HEIGHT, &[create_globe()]
+        ));
+        state.update(std::time::Duration::from_secs(1));
+
+        let pixels = state.render_to_rgba().unwrap();
+        golden::assert_matches(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/golden/textured-sphere.png"),
+            golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+        );
+    }
+}
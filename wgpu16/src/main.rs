@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use cgmath::Deg;
+use learn_wgpu_core::{common, mesh, parametric, transforms, vertex_data};
+use learn_wgpu_core::lights::{Attenuation, LightAnimation, LightSource};
+
+fn create_objects() -> Vec<common::SceneObject> {
+    let torus = parametric::torus(1.2, 0.35, 96, 32).mesh();
+    let torus_material = common::material([1.0, 1.0, 1.0], [1.0, 1.0, 1.0], 0.05, 0.9, 0.6, 40.0);
+    let mut torus = common::SceneObject::indexed(&torus, torus_material);
+    torus.model_mat = transforms::create_transforms(
+        [0.0, 0.2, 0.0], [0.5, 0.0, 0.0], [1.0, 1.0, 1.0]
+    );
+
+    let (positions, normals, indices) = vertex_data::cube_indexed();
+    let floor = mesh::IndexedMesh::new(&positions, &normals, indices);
+    let floor_material = common::material([0.8, 0.8, 0.8], [1.0, 1.0, 1.0], 0.05, 0.8, 0.2, 10.0);
+    let mut floor = common::SceneObject::indexed(&floor, floor_material);
+    floor.model_mat = transforms::create_transforms(
+        [0.0, -1.0, 0.0], [0.0, 0.0, 0.0], [2.5, 0.05, 2.5]
+    );
+
+    vec![torus, floor]
+}
+
+fn create_lights() -> Vec<LightSource> {
+    vec![
+        // dim warm sunlight from the side
+        LightSource::directional([-1.0, -1.0, 0.5], [1.0, 0.9, 0.7]).with_intensity(0.3),
+        LightSource::point([2.0, 0.5, 0.0], [1.0, 0.2, 0.2])
+            .with_intensity(1.2)
+            .with_attenuation(Attenuation::range(20.0)),
+        LightSource::point([-2.0, 0.5, 0.0], [0.2, 1.0, 0.3])
+            .with_intensity(1.2)
+            .with_attenuation(Attenuation::range(20.0)),
+        LightSource::spot([0.0, 3.0, 0.0], [0.0, -1.0, 0.0], [0.3, 0.5, 1.0], Deg(15.0), Deg(25.0))
+            .with_intensity(3.0),
+    ]
+}
+
+/// Circles the point lights around the scene in opposite directions and
+/// sweeps the spot light along the floor.
+fn animate_lights() -> LightAnimation {
+    Box::new(|lights: &mut [LightSource], t: Duration| {
+        let t = t.as_secs_f32();
+        let (s, c) = (0.8 * t).sin_cos();
+        lights[1].set_position([2.0 * c, 0.5, 2.0 * s]);
+        lights[2].set_position([-2.0 * c, 0.5, 2.0 * s]);
+        if let LightSource::Spot { direction, .. } = &mut lights[3] {
+            *direction = [0.2 * (0.5 * t).sin(), -1.0, 0.2 * (0.5 * t).cos()];
+        }
+    })
+}
+
+fn main() {
+    let objects = create_objects();
+    let lights = create_lights();
+    common::run_scene_with_lights(
+        &objects, Some(&lights), Some(animate_lights()), "Point, Directional and Spot Lights"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use learn_wgpu_core::depth::DepthConfig;
+    use learn_wgpu_core::golden;
+
+    #[test]
+    fn multiple_lights_match_golden() {
+        let mut state = pollster::block_on(common::State::new_offscreen_scene(
+            golden::WIDTH, golden::HEIGHT, &create_objects()
+        ));
+        state.set_lights(&create_lights());
+        state.set_light_animation(animate_lights());
+        state.update(Duration::from_secs(1));
+        assert_ne!(state.lights_mut()[1], create_lights()[1]);
+        // back to the start, where the floor is level
+        state.update(Duration::ZERO);
+
+        let pixels = state.render_to_rgba().unwrap();
+        golden::assert_matches(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/golden/multiple-lights.png"),
+            golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+        );
+    }
+
+    #[test]
+    fn depth_formats_match_golden() {
+        let configs = [
+            DepthConfig::new(wgpu::TextureFormat::Depth32Float, true),
+            DepthConfig::new(wgpu::TextureFormat::Depth24PlusStencil8, false),
+        ];
+        for config in configs {
+            let mut state = pollster::block_on(common::State::new_offscreen_scene(
+                golden::WIDTH, golden::HEIGHT, &create_objects()
+            ));
+            state.set_lights(&create_lights());
+            state.set_light_animation(animate_lights());
+            state.set_depth(config);
+            state.update(Duration::ZERO);
+
+            let pixels = state.render_to_rgba().unwrap();
+            golden::assert_matches(
+                concat!(env!("CARGO_MANIFEST_DIR"), "/golden/multiple-lights.png"),
+                golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+            );
+        }
+    }
+}
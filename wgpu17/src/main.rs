@@ -0,0 +1,95 @@
+use learn_wgpu_core::{common, transforms, vertex_data};
+use learn_wgpu_core::lights::{Attenuation, LightSource};
+use learn_wgpu_core::material::Material;
+
+fn create_vertices(r: f32, u: usize, v: usize) -> Vec<common::TexturedVertex> {
+    let (pos, normal, uvs) = vertex_data::sphere_data(r, u, v);
+    pos.iter()
+        .zip(normal.iter())
+        .zip(uvs.iter())
+        .map(|((p, n), uv)| common::TexturedVertex {
+            position: [p[0], p[1], p[2], 1.0],
+            normal: [n[0], n[1], n[2], 1.0],
+            uv: *uv,
+        })
+        .collect()
+}
+
+/// `n` by `n` squares alternating between the two colors.
+fn checker(size: u32, n: u32, a: [u8; 3], b: [u8; 3]) -> image::RgbaImage {
+    image::RgbaImage::from_fn(size, size, |x, y| {
+        let [r, g, b] = if (x * n / size + y * n / size).is_multiple_of(2) { a } else { b };
+        image::Rgba([r, g, b, 255])
+    })
+}
+
+fn create_materials() -> [Material; 3] {
+    // plastic: colored body, white highlights
+    let plastic = common::material([0.9, 0.1, 0.1], [1.0, 1.0, 1.0], 0.1, 0.8, 0.6, 40.0);
+    // metal: highlights tinted by the body color
+    let gold = Material {
+        ambient: [0.1, 0.08, 0.02],
+        diffuse: [0.35, 0.27, 0.08],
+        specular: [1.0, 0.8, 0.3],
+        shininess: 120.0,
+        diffuse_texture: None,
+        specular_texture: None,
+    };
+    // tiles: only the light tiles shine
+    let tiles = Material::new([0.9, 0.9, 0.9], [1.0, 1.0, 1.0], 60.0)
+        .with_diffuse_texture(checker(64, 8, [230, 230, 230], [40, 90, 200]))
+        .with_specular_texture(checker(64, 8, [255, 255, 255], [0, 0, 0]));
+    [plastic, gold, tiles]
+}
+
+fn create_objects() -> Vec<common::SceneObject> {
+    let vertex_data = create_vertices(0.8, 31, 61);
+    let offsets = [-1.6, 0.0, 1.6];
+    create_materials()
+        .into_iter()
+        .zip(offsets)
+        .map(|(material, offset)| {
+            let mut object = common::SceneObject::textured(&vertex_data, material);
+            // a row that starts out facing the camera
+            object.model_mat = transforms::create_transforms(
+                [offset, 0.0, 0.0], [0.0, 0.0, 0.0], [1.0, 1.0, 1.0]
+            );
+            object
+        })
+        .collect()
+}
+
+fn create_lights() -> Vec<LightSource> {
+    vec![
+        LightSource::point([2.0, 3.0, 4.0], [1.0, 1.0, 1.0])
+            .with_intensity(1.5)
+            .with_attenuation(Attenuation::range(30.0)),
+        LightSource::directional([1.0, -0.5, 1.0], [0.6, 0.7, 1.0]).with_intensity(0.3),
+    ]
+}
+
+fn main() {
+    let lights = create_lights();
+    common::run_scene_with_lights(&create_objects(), Some(&lights), None, "Materials");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use learn_wgpu_core::golden;
+
+    #[test]
+    fn materials_match_golden() {
+        let mut state = pollster::block_on(common::State::new_offscreen_scene(
+            golden::WIDTH, golden::HEIGHT, &create_objects()
+        ));
+        state.set_lights(&create_lights());
+        state.update(std::time::Duration::ZERO);
+
+        let pixels = state.render_to_rgba().unwrap();
+        golden::assert_matches(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/golden/materials.png"),
+            golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+        );
+    }
+}
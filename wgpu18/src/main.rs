@@ -0,0 +1,86 @@
+use learn_wgpu_core::{common, transforms, vertex_data};
+use learn_wgpu_core::lights::{Attenuation, LightSource};
+use learn_wgpu_core::material::PbrMaterial;
+
+const ROWS: usize = 5;
+const COLUMNS: usize = 5;
+const SPACING: f32 = 1.1;
+
+fn create_vertices(r: f32, u: usize, v: usize) -> Vec<common::TexturedVertex> {
+    let (pos, normal, uvs) = vertex_data::sphere_data(r, u, v);
+    pos.iter()
+        .zip(normal.iter())
+        .zip(uvs.iter())
+        .map(|((p, n), uv)| common::TexturedVertex {
+            position: [p[0], p[1], p[2], 1.0],
+            normal: [n[0], n[1], n[2], 1.0],
+            uv: *uv,
+        })
+        .collect()
+}
+
+/// Material balls facing +z: metalness grows from left to right and
+/// roughness from top to bottom.
+fn create_objects() -> Vec<common::SceneObject> {
+    let vertex_data = create_vertices(0.45, 25, 49);
+    let mut objects = Vec::with_capacity(ROWS * COLUMNS);
+    for row in 0..ROWS {
+        for column in 0..COLUMNS {
+            let metallic = column as f32 / (COLUMNS - 1) as f32;
+            let roughness = (row as f32 / (ROWS - 1) as f32).max(0.05);
+            let material = PbrMaterial::new([0.8, 0.1, 0.1], metallic, roughness);
+            let mut object = common::SceneObject::textured(&vertex_data, material);
+            let x = (column as f32 - 0.5 * (COLUMNS - 1) as f32) * SPACING;
+            let y = (0.5 * (ROWS - 1) as f32 - row as f32) * SPACING;
+            object.model_mat = transforms::create_transforms(
+                [x, y, 0.0], [0.0, 0.0, 0.0], [1.0, 1.0, 1.0]
+            );
+            objects.push(object);
+        }
+    }
+    objects
+}
+
+fn create_lights() -> Vec<LightSource> {
+    [[-3.0, 3.0, 4.0], [3.0, 3.0, 4.0], [-3.0, -3.0, 4.0], [3.0, -3.0, 4.0]]
+        .into_iter()
+        .map(|position| {
+            LightSource::point(position, [1.0, 1.0, 1.0])
+                .with_intensity(30.0)
+                .with_attenuation(Attenuation { constant: 0.0, linear: 0.0, quadratic: 1.0 })
+        })
+        .collect()
+}
+
+fn setup(state: &mut common::State) {
+    state.set_lights(&create_lights());
+    state.set_spin(false);
+    let camera = state.camera_mut();
+    camera.eye = (0.0, 0.0, 5.0).into();
+    camera.target = (0.0, 0.0, 0.0).into();
+}
+
+fn main() {
+    common::run_scene_with(&create_objects(), "PBR Material Balls", setup);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use learn_wgpu_core::golden;
+
+    #[test]
+    fn material_balls_match_golden() {
+        let mut state = pollster::block_on(common::State::new_offscreen_scene(
+            golden::WIDTH, golden::HEIGHT, &create_objects()
+        ));
+        setup(&mut state);
+        state.update(std::time::Duration::ZERO);
+
+        let pixels = state.render_to_rgba().unwrap();
+        golden::assert_matches(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/golden/material-balls.png"),
+            golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+        );
+    }
+}
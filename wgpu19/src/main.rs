@@ -0,0 +1,74 @@
+use cgmath::Deg;
+use learn_wgpu_core::{common, mesh, parametric, transforms, vertex_data};
+use learn_wgpu_core::lights::{Attenuation, LightSource};
+use learn_wgpu_core::shadow::ShadowConfig;
+
+fn create_objects() -> Vec<common::SceneObject> {
+    let torus = parametric::torus(1.0, 0.3, 96, 32).mesh();
+    let torus_material = common::material([0.9, 0.6, 0.2], [1.0, 1.0, 1.0], 0.1, 0.9, 0.5, 40.0);
+    let mut torus = common::SceneObject::indexed(&torus, torus_material);
+    // tilted so its shadow is an ellipse with a hole
+    torus.model_mat = transforms::create_transforms(
+        [0.0, 0.3, 0.0], [0.6, 0.0, 0.3], [1.0, 1.0, 1.0]
+    );
+
+    let (positions, normals, indices) = vertex_data::cube_indexed();
+    let ground = mesh::IndexedMesh::new(&positions, &normals, indices);
+    let ground_material = common::material([0.8, 0.8, 0.8], [1.0, 1.0, 1.0], 0.1, 0.8, 0.2, 10.0);
+    let mut ground = common::SceneObject::indexed(&ground, ground_material);
+    ground.model_mat = transforms::create_transforms(
+        [0.0, -1.0, 0.0], [0.0, 0.0, 0.0], [3.0, 0.05, 3.0]
+    );
+
+    vec![torus, ground]
+}
+
+fn create_lights() -> Vec<LightSource> {
+    vec![
+        // low sun casting a long shadow
+        LightSource::directional([1.0, -1.5, -0.6], [1.0, 0.95, 0.85]).with_intensity(0.8),
+        // blue spot from above, its shadow falling straight down
+        LightSource::spot([0.5, 3.5, 0.5], [-0.1, -1.0, -0.1], [0.4, 0.6, 1.0], Deg(25.0), Deg(35.0))
+            .with_intensity(1.5)
+            .with_attenuation(Attenuation::range(30.0)),
+    ]
+}
+
+fn create_shadows() -> ShadowConfig {
+    // a sphere around the ground, which holds the torus too
+    ShadowConfig { center: [0.0, -0.5, 0.0], radius: 4.4, ..Default::default() }
+}
+
+fn setup(state: &mut common::State) {
+    state.set_lights(&create_lights());
+    state.set_shadows(Some(create_shadows()));
+    state.set_spin(false);
+    let camera = state.camera_mut();
+    camera.eye = (3.5, 3.0, 4.5).into();
+    camera.target = (0.0, -0.5, 0.0).into();
+}
+
+fn main() {
+    common::run_scene_with(&create_objects(), "Shadow Mapping", setup);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use learn_wgpu_core::golden;
+
+    #[test]
+    fn shadows_match_golden() {
+        let mut state = pollster::block_on(common::State::new_offscreen_scene(
+            golden::WIDTH, golden::HEIGHT, &create_objects()
+        ));
+        setup(&mut state);
+        state.update(std::time::Duration::ZERO);
+
+        let pixels = state.render_to_rgba().unwrap();
+        golden::assert_matches(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/golden/shadows.png"),
+            golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+        );
+    }
+}
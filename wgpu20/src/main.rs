@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use cgmath::Matrix4;
+use learn_wgpu_core::{common, vertex_data};
+use learn_wgpu_core::instance::{Instance, InstanceAnimation};
+use learn_wgpu_core::lights::LightSource;
+
+const GRID: usize = 60;
+const SPACING: f32 = 0.12;
+const CUBE_SIZE: f32 = 0.045;
+
+fn create_vertices() -> Vec<common::Vertex> {
+    let pos = vertex_data::cube_positions();
+    let normal = vertex_data::cube_normals();
+    pos.iter()
+        .zip(normal.iter())
+        .map(|(p, n)| common::Vertex {
+            position: [p[0] as f32, p[1] as f32, p[2] as f32, 1.0],
+            normal: [n[0] as f32, n[1] as f32, n[2] as f32, 1.0],
+        })
+        .collect()
+}
+
+/// A white cube, colored per instance.
+fn create_cube() -> common::SceneObject {
+    let material = common::material([1.0, 1.0, 1.0], [1.0, 1.0, 1.0], 0.15, 0.8, 0.4, 30.0);
+    common::SceneObject::new(&create_vertices(), material)
+}
+
+/// Grid position of the `i`th cube, centered on the origin.
+fn grid_position(i: usize) -> (f32, f32) {
+    let offset = 0.5 * (GRID - 1) as f32;
+    let x = (i % GRID) as f32 - offset;
+    let z = (i / GRID) as f32 - offset;
+    (x * SPACING, z * SPACING)
+}
+
+/// A cube bobbing on a ripple spreading from the center, colored from
+/// blue in the troughs to yellow on the crests.
+fn wave_instance(i: usize, t: f32) -> Instance {
+    let (x, z) = grid_position(i);
+    let height = (4.0 * (x * x + z * z).sqrt() - 2.0 * t).sin();
+    let model_mat = Matrix4::from_translation([x, 0.25 * height, z].into())
+        * Matrix4::from_angle_y(cgmath::Rad(x + t))
+        * Matrix4::from_scale(CUBE_SIZE);
+    let s = 0.5 + 0.5 * height;
+    Instance::new(model_mat, [0.2 + 0.8 * s, 0.4 + 0.5 * s, 1.0 - 0.8 * s])
+}
+
+fn create_instances() -> Vec<Instance> {
+    (0..GRID * GRID).map(|i| wave_instance(i, 0.0)).collect()
+}
+
+fn animate_instances() -> InstanceAnimation {
+    Box::new(|instances: &mut [Instance], t: Duration| {
+        let t = t.as_secs_f32();
+        for (i, instance) in instances.iter_mut().enumerate() {
+            *instance = wave_instance(i, t);
+        }
+    })
+}
+
+fn setup(state: &mut common::State) {
+    state.add_instances(0, &create_instances());
+    state.set_instance_animation(0, animate_instances());
+    state.set_lights(&[
+        LightSource::directional([-0.5, -1.0, -0.3], [1.0, 1.0, 1.0]),
+        LightSource::point([0.0, 3.0, 4.0], [1.0, 1.0, 1.0]).with_intensity(0.4),
+    ]);
+    state.set_spin(false);
+    let camera = state.camera_mut();
+    camera.eye = (0.0, 4.0, 6.0).into();
+    camera.target = (0.0, -0.5, 0.0).into();
+}
+
+fn main() {
+    common::run_scene_with(&[create_cube()], "GPU Instancing", setup);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use learn_wgpu_core::golden;
+
+    #[test]
+    fn instanced_cubes_match_golden() {
+        let mut state = pollster::block_on(common::State::new_offscreen_scene(
+            golden::WIDTH, golden::HEIGHT, &[create_cube()]
+        ));
+        setup(&mut state);
+        assert_eq!(state.instances_mut(0).len(), GRID * GRID);
+        state.update(Duration::from_millis(1500));
+        assert_eq!(state.instances_mut(0)[7], wave_instance(7, 1.5));
+
+        let pixels = state.render_to_rgba().unwrap();
+        golden::assert_matches(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/golden/instanced-cubes.png"),
+            golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use cgmath::{Quaternion, Rad, Rotation3};
+use learn_wgpu_core::{common, parametric};
+use learn_wgpu_core::lights::LightSource;
+use learn_wgpu_core::scene_graph::{GraphAnimation, Mesh, NodeId, SceneGraph};
+
+const EARTH_ORBIT: f32 = 3.0;
+const MOON_ORBIT: f32 = 0.8;
+const MARS_ORBIT: f32 = 4.8;
+
+/// The nodes moved by the animation. Each orbit is a pivot at the center
+/// of the orbit, so turning it carries everything below it along.
+#[derive(Copy, Clone, Debug)]
+struct SolarSystem {
+    sun: NodeId,
+    earth_pivot: NodeId,
+    earth: NodeId,
+    moon_pivot: NodeId,
+    mars_pivot: NodeId,
+}
+
+fn create_graph() -> (SceneGraph, SolarSystem) {
+    let mut graph = SceneGraph::new();
+    // one unit sphere drawn at every body's scale
+    let sphere = graph.add_mesh(Mesh::indexed(&parametric::sphere(1.0, 24, 48).mesh()));
+    // the sun is lit from the inside, so it glows by its ambient color
+    let sun_material = graph.add_material(
+        common::material([1.0, 0.8, 0.3], [0.0, 0.0, 0.0], 1.0, 0.0, 0.0, 1.0)
+    );
+    let earth_material = graph.add_material(
+        common::material([0.2, 0.4, 1.0], [1.0, 1.0, 1.0], 0.05, 0.9, 0.4, 30.0)
+    );
+    let moon_material = graph.add_material(
+        common::material([0.7, 0.7, 0.7], [1.0, 1.0, 1.0], 0.05, 0.9, 0.1, 10.0)
+    );
+    let mars_material = graph.add_material(
+        common::material([0.9, 0.35, 0.15], [1.0, 1.0, 1.0], 0.05, 0.9, 0.2, 20.0)
+    );
+
+    let sun = graph.add_node(None, "sun");
+    graph.set_mesh(sun, sphere, sun_material);
+
+    let earth_pivot = graph.add_node(None, "earth orbit");
+    // the earth and the moon's orbit around it, unscaled
+    let earth_system = graph.add_node(Some(earth_pivot), "earth system");
+    graph.set_translation(earth_system, [EARTH_ORBIT, 0.0, 0.0]);
+    let earth = graph.add_node(Some(earth_system), "earth");
+    graph.set_scale(earth, [0.4; 3]);
+    graph.set_mesh(earth, sphere, earth_material);
+    let moon_pivot = graph.add_node(Some(earth_system), "moon orbit");
+    let moon = graph.add_node(Some(moon_pivot), "moon");
+    graph.set_translation(moon, [MOON_ORBIT, 0.0, 0.0]);
+    graph.set_scale(moon, [0.12; 3]);
+    graph.set_mesh(moon, sphere, moon_material);
+
+    let mars_pivot = graph.add_node(None, "mars orbit");
+    let mars = graph.add_node(Some(mars_pivot), "mars");
+    graph.set_translation(mars, [MARS_ORBIT, 0.0, 0.0]);
+    graph.set_scale(mars, [0.3; 3]);
+    graph.set_mesh(mars, sphere, mars_material);
+
+    let system = SolarSystem { sun, earth_pivot, earth, moon_pivot, mars_pivot };
+    orbit(&mut graph, &system, 0.0);
+    (graph, system)
+}
+
+/// Places the bodies `t` seconds in; the moon circles the earth several
+/// times per year.
+fn orbit(graph: &mut SceneGraph, system: &SolarSystem, t: f32) {
+    let spin = |speed: f32, phase: f32| Quaternion::from_angle_y(Rad(speed * t + phase));
+    graph.set_rotation(system.sun, spin(0.2, 0.0));
+    graph.set_rotation(system.earth_pivot, spin(0.5, 0.0));
+    graph.set_rotation(system.earth, spin(2.0, 0.0));
+    graph.set_rotation(system.moon_pivot, spin(2.5, 0.0));
+    graph.set_rotation(system.mars_pivot, spin(0.3, 2.0));
+}
+
+fn animate(system: SolarSystem) -> GraphAnimation {
+    Box::new(move |graph: &mut SceneGraph, t: Duration| {
+        orbit(graph, &system, t.as_secs_f32());
+    })
+}
+
+fn setup(state: &mut common::State, system: SolarSystem) {
+    state.set_graph_animation(animate(system));
+    state.set_lights(&[
+        LightSource::point([0.0, 0.0, 0.0], [1.0, 0.95, 0.85]).with_intensity(1.5),
+    ]);
+    state.set_spin(false);
+    let camera = state.camera_mut();
+    camera.eye = (0.0, 5.0, 8.0).into();
+    camera.target = (0.0, -0.5, 0.0).into();
+}
+
+fn main() {
+    let (graph, system) = create_graph();
+    common::run_graph_with(graph, "Scene Graph", |state| setup(state, system));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{InnerSpace, Vector4};
+    use learn_wgpu_core::golden;
+
+    fn position(graph: &SceneGraph, node: NodeId) -> Vector4<f32> {
+        graph.node(node).world_matrix() * Vector4::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    #[test]
+    fn solar_system_matches_golden() {
+        let (graph, system) = create_graph();
+        let mut state = pollster::block_on(common::State::new_offscreen_graph(
+            golden::WIDTH, golden::HEIGHT, graph
+        ));
+        setup(&mut state, system);
+        state.update(Duration::from_secs(2));
+
+        // the moon stays on its orbit around the moving earth
+        let graph = state.graph();
+        let earth = position(graph, system.earth);
+        let (moon, _) = graph.nodes().find(|(_, node)| node.name == "moon").unwrap();
+        let moon = position(graph, moon);
+        assert!((earth.truncate().magnitude() - EARTH_ORBIT).abs() < 1e-4);
+        assert!(((moon - earth).magnitude() - MOON_ORBIT).abs() < 1e-4);
+
+        let pixels = state.render_to_rgba().unwrap();
+        golden::assert_matches(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/golden/solar-system.png"),
+            golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+        );
+    }
+
+    #[test]
+    fn zero_scale_nodes_are_drawn_as_nothing() {
+        let (graph, system) = create_graph();
+        let mut state = pollster::block_on(common::State::new_offscreen_graph(
+            golden::WIDTH, golden::HEIGHT, graph
+        ));
+        setup(&mut state, system);
+        // the earth's and the moon's world matrices have no inverse
+        let graph = state.graph_mut();
+        let (earth_system, _) = graph.nodes().find(|(_, node)| node.name == "earth system").unwrap();
+        graph.set_scale(earth_system, [0.0; 3]);
+        state.update(Duration::from_secs(2));
+        state.render_to_rgba().unwrap();
+    }
+}
@@ -0,0 +1,153 @@
+use cgmath::{Deg, Quaternion, Rotation3, Vector3};
+use learn_wgpu_core::{common, mesh, parametric, vertex_data};
+use learn_wgpu_core::animation::{Channel, Clip, Easing, Interpolation, Repeat, Track};
+use learn_wgpu_core::lights::{Attenuation, LightSource};
+use learn_wgpu_core::scene_graph::{Mesh, NodeId, SceneGraph};
+
+/// Seconds for the cube to go once around the floor.
+const LAP: f32 = 4.0;
+
+#[derive(Copy, Clone, Debug)]
+struct Nodes {
+    cube: NodeId,
+    ball: NodeId,
+}
+
+fn create_graph() -> (SceneGraph, Nodes) {
+    let mut graph = SceneGraph::new();
+    let (positions, normals, indices) = vertex_data::cube_indexed();
+    let cube_mesh = graph.add_mesh(Mesh::indexed(
+        &mesh::IndexedMesh::new(&positions, &normals, indices)
+    ));
+    let sphere_mesh = graph.add_mesh(Mesh::indexed(&parametric::sphere(1.0, 24, 48).mesh()));
+    let white = graph.add_material(
+        common::material([0.9, 0.9, 0.9], [1.0, 1.0, 1.0], 0.1, 0.8, 0.2, 10.0)
+    );
+    let orange = graph.add_material(
+        common::material([1.0, 0.5, 0.1], [1.0, 1.0, 1.0], 0.1, 0.9, 0.5, 30.0)
+    );
+
+    let floor = graph.add_node(None, "floor");
+    graph.set_translation(floor, [0.0, -1.0, 0.0]);
+    graph.set_scale(floor, [2.5, 0.05, 2.5]);
+    graph.set_mesh(floor, cube_mesh, white);
+
+    let cube = graph.add_node(None, "cube");
+    graph.set_mesh(cube, cube_mesh, orange);
+    let ball = graph.add_node(None, "ball");
+    graph.set_scale(ball, [0.35; 3]);
+    graph.set_mesh(ball, sphere_mesh, white);
+    (graph, Nodes { cube, ball })
+}
+
+/// The cube hops around the floor on a smooth path, tumbling and landing
+/// squashed, while the ball bobs above the center.
+fn create_clips(nodes: Nodes) -> Vec<Clip> {
+    let corner = |x: f32, z: f32| [1.5 * x, -0.6, 1.5 * z];
+    let mut path = Track::new(Interpolation::Cubic);
+    for (i, p) in [(1.0, 1.0), (1.0, -1.0), (-1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)]
+        .into_iter()
+        .enumerate()
+    {
+        path = path.key(i as f32 * LAP / 4.0, corner(p.0, p.1));
+    }
+
+    let axis = Vector3::new(1.0, 1.0, 0.0) / 2.0_f32.sqrt();
+    let mut tumble = Track::new(Interpolation::Slerp);
+    for i in 0..=4 {
+        let angle = Deg(90.0 * i as f32);
+        tumble = tumble.eased_key(
+            i as f32 * LAP / 4.0, Quaternion::from_axis_angle(axis, angle), Easing::EaseInOut
+        );
+    }
+
+    // squashed for a moment at every corner
+    let mut squash = Track::new(Interpolation::Step);
+    for i in 0..4 {
+        let t = i as f32 * LAP / 4.0;
+        squash = squash.key(t, [0.45, 0.25, 0.45]).key(t + 0.2, [0.3; 3]);
+    }
+    squash = squash.key(LAP, [0.3; 3]);
+
+    let cube = Clip::new(Repeat::Loop)
+        .with(Channel::Translation(nodes.cube, path))
+        .with(Channel::Rotation(nodes.cube, tumble))
+        .with(Channel::Scale(nodes.cube, squash))
+        // the lamp cools down and warms up again along the lap
+        .with(Channel::LightColor(0, Track::new(Interpolation::Linear)
+            .key(0.0, [1.0, 0.6, 0.3])
+            .key(LAP / 2.0, [0.3, 0.6, 1.0])
+            .key(LAP, [1.0, 0.6, 0.3])));
+
+    let ball = Clip::new(Repeat::PingPong)
+        .with(Channel::Translation(nodes.ball, Track::new(Interpolation::Linear)
+            .eased_key(0.0, [0.0, -0.55, 0.0], Easing::EaseOut)
+            .key(0.75, [0.0, 0.8, 0.0])))
+        .with(Channel::LightIntensity(1, Track::new(Interpolation::Linear)
+            .key(0.0, 0.2)
+            .key(0.75, 0.6)));
+    vec![cube, ball]
+}
+
+fn setup(state: &mut common::State, nodes: Nodes) {
+    state.set_lights(&[
+        LightSource::point([0.0, 2.0, 0.0], [1.0, 0.6, 0.3])
+            .with_intensity(2.0)
+            .with_attenuation(Attenuation::range(12.0)),
+        LightSource::directional([-0.4, -1.0, -0.6], [1.0, 1.0, 1.0]),
+    ]);
+    for clip in create_clips(nodes) {
+        state.add_clip(clip);
+    }
+    state.set_spin(false);
+    let camera = state.camera_mut();
+    camera.eye = (0.0, 3.5, 5.0).into();
+    camera.target = (0.0, -0.5, 0.0).into();
+}
+
+fn main() {
+    let (graph, nodes) = create_graph();
+    common::run_graph_with(graph, "Keyframe Animation", |state| setup(state, nodes));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use cgmath::Vector4;
+    use learn_wgpu_core::golden;
+
+    fn position(graph: &SceneGraph, node: NodeId) -> [f32; 3] {
+        let p = graph.node(node).world_matrix() * Vector4::new(0.0, 0.0, 0.0, 1.0);
+        [p.x, p.y, p.z]
+    }
+
+    #[test]
+    fn keyframes_match_golden() {
+        let (graph, nodes) = create_graph();
+        let mut state = pollster::block_on(common::State::new_offscreen_graph(
+            golden::WIDTH, golden::HEIGHT, graph
+        ));
+        setup(&mut state, nodes);
+
+        // the cube is at a corner on every quarter lap, pausing stops the
+        // clock and the ball is back down after going up and down once
+        state.update(Duration::from_secs_f32(LAP / 4.0));
+        assert_eq!(position(state.graph(), nodes.cube), [1.5, -0.6, -1.5]);
+        state.clock_mut().set_paused(true);
+        state.update(Duration::from_secs(3));
+        assert_eq!(state.clock().time(), LAP / 4.0);
+        state.clock_mut().set_paused(false);
+        state.clock_mut().seek(1.5);
+        state.update(Duration::from_secs(3));
+        assert_eq!(position(state.graph(), nodes.ball), [0.0, -0.55, 0.0]);
+
+        state.clock_mut().seek(2.6);
+        state.update(Duration::from_secs(3));
+        let pixels = state.render_to_rgba().unwrap();
+        golden::assert_matches(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/golden/keyframes.png"),
+            golden::WIDTH, golden::HEIGHT, &pixels, golden::DEFAULT_TOLERANCE
+        );
+    }
+}
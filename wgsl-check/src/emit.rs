@@ -0,0 +1,96 @@
+//! Translation of a validated module into what each wgpu backend receives.
+
+use naga::back::{glsl, hlsl, msl, spv};
+use naga::valid::ModuleInfo;
+use naga::{Module, ShaderStage};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    SpirV,
+    Glsl,
+    Msl,
+    Hlsl,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 4] = [Backend::SpirV, Backend::Glsl, Backend::Msl, Backend::Hlsl];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "spv" | "spirv" => Some(Backend::SpirV),
+            "glsl" => Some(Backend::Glsl),
+            "msl" | "metal" => Some(Backend::Msl),
+            "hlsl" => Some(Backend::Hlsl),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::SpirV => "SPIR-V",
+            Backend::Glsl => "GLSL",
+            Backend::Msl => "MSL",
+            Backend::Hlsl => "HLSL",
+        }
+    }
+}
+
+/// Output files as `(file name suffix, contents)`. GLSL has one file per
+/// entry point; the other languages hold all of them in one file.
+pub fn emit(
+    backend: Backend, module: &Module, info: &ModuleInfo
+) -> Result<Vec<(String, Vec<u8>)>, String> {
+    match backend {
+        Backend::SpirV => {
+            let words = spv::write_vec(module, info, &spv::Options::default(), None)
+                .map_err(|e| e.to_string())?;
+            let bytes = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+            Ok(vec![("spv".to_string(), bytes)])
+        },
+        Backend::Glsl => module
+            .entry_points
+            .iter()
+            .map(|entry_point| {
+                let mut source = String::new();
+                let options = glsl::Options::default();
+                let pipeline_options = glsl::PipelineOptions {
+                    shader_stage: entry_point.stage,
+                    entry_point: entry_point.name.clone(),
+                    multiview: None,
+                };
+                glsl::Writer::new(
+                    &mut source, module, info, &options, &pipeline_options,
+                    naga::proc::BoundsCheckPolicies::default()
+                )
+                    .and_then(|mut writer| writer.write())
+                    .map_err(|e| format!("{}: {}", entry_point.name, e))?;
+                Ok((format!("{}.glsl", stage_extension(entry_point.stage)), source.into_bytes()))
+            })
+            .collect(),
+        Backend::Msl => {
+            let (source, _) = msl::write_string(
+                module, info, &msl::Options::default(), &msl::PipelineOptions::default()
+            )
+                .map_err(|e| e.to_string())?;
+            Ok(vec![("metal".to_string(), source.into_bytes())])
+        },
+        Backend::Hlsl => {
+            let mut source = String::new();
+            let options = hlsl::Options::default();
+            let pipeline_options = hlsl::PipelineOptions::default();
+            hlsl::Writer::new(&mut source, &options, &pipeline_options)
+                .write(module, info, None)
+                .map_err(|e| e.to_string())?;
+            Ok(vec![("hlsl".to_string(), source.into_bytes())])
+        },
+    }
+}
+
+fn stage_extension(stage: ShaderStage) -> &'static str {
+    match stage {
+        ShaderStage::Vertex => "vert",
+        ShaderStage::Fragment => "frag",
+        ShaderStage::Compute => "comp",
+        _ => "shader",
+    }
+}
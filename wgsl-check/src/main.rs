@@ -0,0 +1,284 @@
+//! Validates the WGSL shaders of the workspace with naga and
+//! optionally translates them for each wgpu backend:
+//!
+//! ```sh
+//! cargo run --bin wgsl-check -- [shader.wgsl ...] [--emit spv,glsl,msl,hlsl|all] [--out-dir dir]
+//! ```
+//!
+//! Without paths every `.wgsl` file in the workspace is checked. Output
+//! goes to `target/shaders/<crate>/` unless `--out-dir` is given.
+
+mod emit;
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use learn_wgpu_core::shader;
+use naga::ShaderStage;
+
+use emit::Backend;
+
+fn workspace_dir() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap()
+}
+
+/// Entry points every pipeline in the workspace is created with. Depth-only
+/// pipelines, like the shadow pass, have no fragment stage.
+const ENTRY_POINTS: [(&str, ShaderStage); 2] = [
+    ("vs_main", ShaderStage::Vertex),
+    ("fs_main", ShaderStage::Fragment),
+];
+
+#[derive(Debug)]
+enum CheckError {
+    Read(std::io::Error),
+    /// Parse or validation diagnostic, with source spans.
+    Invalid(String),
+    MissingEntryPoint { name: &'static str, stage: ShaderStage },
+    Emit { backend: Backend, message: String },
+    Write { path: PathBuf, error: std::io::Error },
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckError::Read(e) => write!(f, "failed to read: {}", e),
+            CheckError::Invalid(diagnostic) => write!(f, "{}", diagnostic.trim_end()),
+            CheckError::MissingEntryPoint { name, stage } =>
+                write!(f, "missing {:?} entry point `{}`", stage, name),
+            CheckError::Emit { backend, message } =>
+                write!(f, "{} translation failed: {}", backend.name(), message),
+            CheckError::Write { path, error } =>
+                write!(f, "failed to write {}: {}", path.display(), error),
+        }
+    }
+}
+
+impl std::error::Error for CheckError {}
+
+struct Args {
+    paths: Vec<PathBuf>,
+    backends: Vec<Backend>,
+    out_dir: PathBuf,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: wgsl-check [shader.wgsl ...] [--emit spv,glsl,msl,hlsl|all] [--out-dir dir]"
+    );
+    std::process::exit(2);
+}
+
+fn parse_args() -> Args {
+    let mut parsed = Args {
+        paths: Vec::new(),
+        backends: Vec::new(),
+        out_dir: workspace_dir().join("target/shaders"),
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--emit" => {
+                let list = args.next().unwrap_or_else(|| usage());
+                for name in list.split(',') {
+                    let backends = match name {
+                        "all" => Backend::ALL.to_vec(),
+                        _ => vec![Backend::from_name(name).unwrap_or_else(|| usage())],
+                    };
+                    for backend in backends {
+                        if !parsed.backends.contains(&backend) {
+                            parsed.backends.push(backend);
+                        }
+                    }
+                }
+            },
+            "--out-dir" => parsed.out_dir = args.next().unwrap_or_else(|| usage()).into(),
+            _ if arg.starts_with("--") => usage(),
+            _ => parsed.paths.push(arg.into()),
+        }
+    }
+    parsed
+}
+
+/// Every `.wgsl` file below `dir`, skipping build output and hidden
+/// directories.
+fn find_shaders(dir: &Path) -> Vec<PathBuf> {
+    let mut shaders = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else { return shaders };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if path.is_dir() {
+            if name != "target" && !name.starts_with('.') {
+                shaders.extend(find_shaders(&path));
+            }
+        } else if name.ends_with(".wgsl") {
+            shaders.push(path);
+        }
+    }
+    shaders.sort();
+    shaders
+}
+
+/// The shader as the renderer compiles it: the core's lit shaders are
+/// appended to the shared `common.wgsl`.
+fn read_source(path: &Path) -> std::io::Result<String> {
+    let source = std::fs::read_to_string(path)?;
+    let core = Path::new("learn-wgpu-core/src");
+    if path.ends_with(core.join("shader.wgsl")) || path.ends_with(core.join("pbr.wgsl")) {
+        return Ok(std::fs::read_to_string(path.with_file_name("common.wgsl"))? + &source);
+    }
+    Ok(source)
+}
+
+/// Validates one shader and returns its translations as `(suffix, bytes)`.
+fn check(path: &Path, backends: &[Backend]) -> Result<Vec<(String, Vec<u8>)>, CheckError> {
+    let source = read_source(path).map_err(CheckError::Read)?;
+    let (module, info) = shader::validate_wgsl(&source, &path.display().to_string())
+        .map_err(CheckError::Invalid)?;
+
+    for (name, stage) in ENTRY_POINTS {
+        let has_stage = module.entry_points.iter().any(|e| e.stage == stage);
+        if stage == ShaderStage::Fragment && !has_stage {
+            continue;
+        }
+        if !module.entry_points.iter().any(|e| e.name == name && e.stage == stage) {
+            return Err(CheckError::MissingEntryPoint { name, stage });
+        }
+    }
+
+    // the backends need pipeline-overridable constants resolved, so emit
+    // with their defaults
+    let (module, info) = naga::back::pipeline_constants::process_overrides(
+        &module, &info, None, &Default::default()
+    )
+        .map_err(|e| CheckError::Invalid(e.to_string()))?;
+    let mut outputs = Vec::new();
+    for &backend in backends {
+        let files = emit::emit(backend, &module, &info)
+            .map_err(|message| CheckError::Emit { backend, message })?;
+        outputs.extend(files);
+    }
+    Ok(outputs)
+}
+
+/// Name of the crate a shader belongs to, used as its output directory.
+fn crate_name(path: &Path) -> String {
+    path.ancestors()
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .and_then(|dir| dir.canonicalize().ok())
+        .and_then(|dir| dir.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "shader".to_string())
+}
+
+fn write_outputs(
+    path: &Path, out_dir: &Path, outputs: &[(String, Vec<u8>)]
+) -> Result<Vec<PathBuf>, CheckError> {
+    if outputs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let dir = out_dir.join(crate_name(path));
+    std::fs::create_dir_all(&dir)
+        .map_err(|error| CheckError::Write { path: dir.clone(), error })?;
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    outputs
+        .iter()
+        .map(|(suffix, bytes)| {
+            let file = dir.join(format!("{}.{}", stem, suffix));
+            std::fs::write(&file, bytes)
+                .map_err(|error| CheckError::Write { path: file.clone(), error })?;
+            Ok(file)
+        })
+        .collect()
+}
+
+fn main() {
+    let args = parse_args();
+    let paths = if args.paths.is_empty() {
+        find_shaders(workspace_dir())
+    } else {
+        args.paths.clone()
+    };
+
+    let mut failed = 0;
+    for path in &paths {
+        let shown = path.strip_prefix(workspace_dir()).unwrap_or(path).display();
+        let result = check(path, &args.backends)
+            .and_then(|outputs| write_outputs(path, &args.out_dir, &outputs));
+        match result {
+            Ok(files) => {
+                println!("ok    {}", shown);
+                for file in files {
+                    println!("      -> {}", file.display());
+                }
+            },
+            Err(e) => {
+                failed += 1;
+                println!("FAIL  {}", shown);
+                eprintln!("{}\n", e);
+            },
+        }
+    }
+
+    println!("{} shaders checked, {} failed", paths.len(), failed);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workspace_shaders_validate_and_translate() {
+        let shaders = find_shaders(workspace_dir());
+        assert!(shaders.len() >= 8, "{:?}", shaders);
+        for path in &shaders {
+            let outputs = check(path, &Backend::ALL)
+                .unwrap_or_else(|e| panic!("{}:\n{}", path.display(), e));
+            let suffixes: Vec<_> = outputs.iter().map(|(suffix, _)| suffix.as_str()).collect();
+            let vertex_only = ["shadow.wgsl", "common.wgsl"]
+                .iter()
+                .any(|name| path.ends_with(Path::new("learn-wgpu-core/src").join(name)));
+            if vertex_only {
+                assert_eq!(suffixes, ["spv", "vert.glsl", "metal", "hlsl"]);
+            } else {
+                assert_eq!(suffixes, ["spv", "vert.glsl", "frag.glsl", "metal", "hlsl"]);
+            }
+            // SPIR-V magic number
+            assert_eq!(outputs[0].1[..4], 0x0723_0203u32.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn reports_misnamed_fragment_entry_point() {
+        let path = std::env::temp_dir().join(format!("check-fs-{}.wgsl", std::process::id()));
+        std::fs::write(
+            &path,
+            "@vertex\nfn vs_main() -> @builtin(position) vec4<f32> { return vec4<f32>(); }\n\
+             @fragment\nfn main() -> @location(0) vec4<f32> { return vec4<f32>(); }\n"
+        ).unwrap();
+        let error = check(&path, &[]).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(
+            matches!(error, CheckError::MissingEntryPoint { name: "fs_main", .. }),
+            "{}", error
+        );
+    }
+
+    #[test]
+    fn reports_missing_entry_point() {
+        let path = std::env::temp_dir().join(format!("check-{}.wgsl", std::process::id()));
+        std::fs::write(
+            &path, "@vertex\nfn main() -> @builtin(position) vec4<f32> { return vec4<f32>(); }\n"
+        ).unwrap();
+        let error = check(&path, &[]).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(
+            matches!(error, CheckError::MissingEntryPoint { name: "vs_main", .. }),
+            "{}", error
+        );
+    }
+}